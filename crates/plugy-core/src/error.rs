@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// An error raised by the host while servicing a context call from the guest.
+///
+/// The host glue generated by `#[context]` never traps on bad guest input.
+/// Instead, every context call answers with a `Result<T, ContextError>`
+/// envelope, which the guest-side stubs unpack. Context methods that return
+/// a `Result<T, E>` surface these errors to the plugin as `E`, provided
+/// `E: From<ContextError>`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ContextError {
+    /// The arguments could not be read from the guest memory.
+    Memory(String),
+    /// The arguments sent by the guest could not be deserialized.
+    Deserialize(String),
+    /// The value returned by the context method could not be serialized.
+    Serialize(String),
+}
+
+impl fmt::Display for ContextError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContextError::Memory(e) => write!(f, "could not access guest memory: {e}"),
+            ContextError::Deserialize(e) => write!(f, "could not deserialize arguments: {e}"),
+            ContextError::Serialize(e) => write!(f, "could not serialize return value: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ContextError {}
+
+impl From<ContextError> for String {
+    fn from(value: ContextError) -> Self {
+        value.to_string()
+    }
+}
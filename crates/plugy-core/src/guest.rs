@@ -1,4 +1,8 @@
 use crate::bitwise::{from_bitwise, into_bitwise};
+use crate::error::ContextError;
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::HashMap;
 
 /// Allocates a buffer of the specified length and returns a pointer to it.
 ///
@@ -160,3 +164,481 @@ pub unsafe fn read_msg<T: serde::de::DeserializeOwned>(value: u64) -> T {
     let buffer = Vec::from_raw_parts(ptr, len as _, len as _);
     bincode::deserialize(&buffer).expect("invalid bytes provided")
 }
+
+#[cfg(target_arch = "wasm32")]
+extern "C" {
+    fn _plugy_context_take(value: u64);
+}
+
+/// Takes the result of a synchronous context call from the host.
+///
+/// Synchronous context functions cannot allocate in the guest, so they return
+/// the length of their result instead, and the host writes it into a buffer
+/// allocated here.
+///
+/// # Safety
+///
+/// `len` must be the length a synchronous context function just returned.
+#[allow(unused_variables)]
+pub unsafe fn take_context_result<T: serde::de::DeserializeOwned>(len: u64) -> T {
+    #[cfg(target_arch = "wasm32")]
+    {
+        let value = into_bitwise(alloc(len as _) as _, len as _);
+        _plugy_context_take(value);
+        read_msg(value)
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    panic!("You are trying to call wasm methods outside of wasm32")
+}
+
+/// A call routed through the dispatcher a `#[plugin_impl]` exports for its
+/// trait, reaching methods that rely on their default body.
+///
+/// The host sends the qualified method name followed by the arguments, both
+/// serialized with bincode.
+pub struct Dispatch {
+    method: String,
+    input: Vec<u8>,
+}
+
+impl Dispatch {
+    /// Reads a dispatched call handed over by the host.
+    ///
+    /// # Safety
+    ///
+    /// `value` must be a combined representation of a buffer allocated with
+    /// `alloc`, ownership of which is transferred to this function.
+    pub unsafe fn read(value: u64) -> Self {
+        let (ptr, len) = from_bitwise(value);
+        let ptr = std::ptr::with_exposed_provenance_mut::<u8>(ptr as _);
+        let mut input = Vec::from_raw_parts(ptr, len as _, len as _);
+        let method: String = bincode::deserialize(&input).expect("invalid bytes provided");
+        let offset = bincode::serialized_size(&method).expect("invalid bytes provided");
+        input.drain(..offset as usize);
+        Dispatch { method, input }
+    }
+
+    /// The method called, qualified by its trait as in `Greeter::greet`.
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    /// Deserializes the arguments of the call.
+    pub fn args<T: serde::de::DeserializeOwned>(&self) -> T {
+        bincode::deserialize(&self.input).expect("invalid bytes provided")
+    }
+}
+
+#[derive(Default)]
+struct Instances {
+    init: Vec<u8>,
+    values: HashMap<TypeId, Box<dyn Any>>,
+}
+
+thread_local! {
+    static INSTANCES: RefCell<Instances> = RefCell::default();
+}
+
+/// Tells the host which version of the ABI the guest was built against, see
+/// [`ABI_VERSION`](crate::ABI_VERSION).
+#[no_mangle]
+pub extern "C" fn _plugy_abi_version() -> u32 {
+    crate::ABI_VERSION
+}
+
+/// Stores the bytes plugin instances are constructed from.
+///
+/// The host calls this export once the guest is instantiated, with the same
+/// bytes on every restart. Instances constructed from earlier bytes are dropped.
+/// The host then calls the `_plugy_construct_*` exports of `#[plugin_impl]`
+/// and `#[plugin_hooks]`, see [`construct_instance`].
+///
+/// # Safety
+///
+/// `value` must be a combined representation of a buffer allocated with
+/// `alloc`, ownership of which is transferred to this function.
+#[no_mangle]
+pub unsafe extern "C" fn _plugy_init(value: u64) -> u64 {
+    let (ptr, len) = from_bitwise(value);
+    let ptr = std::ptr::with_exposed_provenance_mut::<u8>(ptr as _);
+    let init = Vec::from_raw_parts(ptr, len as _, len as _);
+    set_init(init);
+    write_msg(&())
+}
+
+fn set_init(init: Vec<u8>) {
+    INSTANCES.with(|instances| {
+        *instances.borrow_mut() = Instances {
+            init,
+            values: HashMap::new(),
+        }
+    });
+}
+
+/// Builds a plugin instance with `Default` when the host sent no init bytes,
+/// as `load` and `load_with` do, and deserializes it from them otherwise.
+fn build_instance<T>(init: &[u8]) -> Result<T, String>
+where
+    T: serde::de::DeserializeOwned + Default,
+{
+    if init.is_empty() {
+        return Ok(T::default());
+    }
+    bincode::deserialize(init).map_err(|e| format!("could not construct plugin instance: {e}"))
+}
+
+/// Builds a plugin instance from the config `C` the host sent.
+fn build_configured_instance<C, T>(init: &[u8]) -> Result<T, String>
+where
+    C: serde::de::DeserializeOwned,
+    T: From<C>,
+{
+    bincode::deserialize::<C>(init).map(T::from).map_err(|e| {
+        if init.is_empty() {
+            "the plugin expects a config, load it with `load_with_config`".to_owned()
+        } else {
+            format!("could not read plugin config: {e}")
+        }
+    })
+}
+
+/// Constructs the instance of the plugin type `T` while the plugin loads.
+///
+/// `#[plugin_impl]` and `#[plugin_hooks]` export this as `_plugy_construct_*`,
+/// which the host calls right after [`_plugy_init`], so a plugin that cannot
+/// be constructed fails to load instead of trapping on its first call.
+/// Returns the serialized `Result<(), String>` of the construction.
+pub fn construct_instance<T>() -> u64
+where
+    T: serde::de::DeserializeOwned + Default + 'static,
+{
+    write_msg(&construct_by(build_instance::<T>))
+}
+
+/// Like [`construct_instance`], constructing the instance from the config `C`.
+pub fn construct_configured_instance<C, T>() -> u64
+where
+    C: serde::de::DeserializeOwned,
+    T: From<C> + 'static,
+{
+    write_msg(&construct_by(build_configured_instance::<C, T>))
+}
+
+fn construct_by<T: 'static>(build: impl FnOnce(&[u8]) -> Result<T, String>) -> Result<(), String> {
+    INSTANCES.with(|instances| {
+        let mut instances = instances.borrow_mut();
+        // Several impls of the same plugin type share the instance
+        if !instances.values.contains_key(&TypeId::of::<T>()) {
+            let value = build(&instances.init)?;
+            instances.values.insert(TypeId::of::<T>(), Box::new(value));
+        }
+        Ok(())
+    })
+}
+
+/// Runs `f` with the guest-held instance of the plugin type `T`.
+///
+/// The instance is constructed while the plugin loads, see
+/// [`construct_instance`], and then kept for every following call, so plugins
+/// can hold state across calls. Calls are serialized by the host, which makes
+/// handing out a mutable reference sound.
+///
+/// # Panics
+///
+/// Panics if the instance was not constructed yet and `T` can not be
+/// deserialized from the init bytes.
+///
+/// # Examples
+///
+/// ```no_run
+/// use plugy_core::guest::with_instance;
+/// #[derive(Default, serde::Deserialize)]
+/// struct MyPlugin;
+///
+/// let name = with_instance(|_: &mut MyPlugin| "my-plugin");
+/// ```
+pub fn with_instance<T, R>(f: impl FnOnce(&mut T) -> R) -> R
+where
+    T: serde::de::DeserializeOwned + Default + 'static,
+{
+    with_instance_by(
+        |init| build_instance(init).unwrap_or_else(|e| panic!("{e}")),
+        f,
+    )
+}
+
+/// Runs `f` with the guest-held instance of the plugin type `T`, constructing
+/// it from the configuration `C` the host passed to `load_with_config`.
+///
+/// # Panics
+///
+/// Panics if the instance was not constructed yet and `C` can not be
+/// deserialized from the init bytes.
+///
+/// # Examples
+///
+/// ```no_run
+/// use plugy_core::guest::with_configured_instance;
+/// #[derive(serde::Deserialize)]
+/// struct MyConfig {
+///     greeting: String,
+/// }
+///
+/// struct MyPlugin {
+///     greeting: String,
+/// }
+///
+/// impl From<MyConfig> for MyPlugin {
+///     fn from(config: MyConfig) -> Self {
+///         MyPlugin { greeting: config.greeting }
+///     }
+/// }
+///
+/// let greeting =
+///     with_configured_instance::<MyConfig, _, _>(|plugin: &mut MyPlugin| plugin.greeting.clone());
+/// ```
+pub fn with_configured_instance<C, T, R>(f: impl FnOnce(&mut T) -> R) -> R
+where
+    C: serde::de::DeserializeOwned,
+    T: From<C> + 'static,
+{
+    with_instance_by(
+        |init| build_configured_instance::<C, T>(init).unwrap_or_else(|e| panic!("{e}")),
+        f,
+    )
+}
+
+fn with_instance_by<T: 'static, R>(
+    construct: impl FnOnce(&[u8]) -> T,
+    f: impl FnOnce(&mut T) -> R,
+) -> R {
+    let taken = INSTANCES.with(|instances| {
+        let mut instances = instances.borrow_mut();
+        match instances.values.remove(&TypeId::of::<T>()) {
+            Some(value) => value,
+            None => Box::new(construct(&instances.init)),
+        }
+    });
+    let mut value = taken
+        .downcast::<T>()
+        .expect("plugin instance stored under the wrong type");
+    // The instance is taken out while `f` runs so that `f` may reach other
+    // plugin instances without tripping over the borrow
+    let result = f(&mut value);
+    INSTANCES.with(|instances| {
+        instances
+            .borrow_mut()
+            .values
+            .insert(TypeId::of::<T>(), value)
+    });
+    result
+}
+
+#[cfg(target_arch = "wasm32")]
+#[link(wasm_import_module = "plugy:plugin_data")]
+extern "C" {
+    #[link_name = "get"]
+    fn _plugy_context_plugin_data_get(ptr: u64) -> u64;
+    #[link_name = "set"]
+    fn _plugy_context_plugin_data_set(ptr: u64) -> u64;
+}
+
+/// Fetches the data blob through the built-in `PluginData` context.
+fn get_blob() -> Result<Vec<u8>, ContextError> {
+    #[cfg(target_arch = "wasm32")]
+    {
+        let ptr = write_msg(&());
+        unsafe { read_msg(_plugy_context_plugin_data_get(ptr)) }
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    panic!("You are trying to call wasm methods outside of wasm32")
+}
+
+/// Stores the data blob through the built-in `PluginData` context.
+#[allow(unused_variables)]
+fn set_blob(blob: Vec<u8>) -> Result<(), ContextError> {
+    #[cfg(target_arch = "wasm32")]
+    {
+        let ptr = write_msg(&blob);
+        unsafe { read_msg(_plugy_context_plugin_data_set(ptr)) }
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    panic!("You are trying to call wasm methods outside of wasm32")
+}
+
+/// Reads the plugin's `Plugin::data` blob kept by the host.
+///
+/// The host must link the `plugy_runtime::PluginData` context. The blob is
+/// empty until something was stored in it, which fails to deserialize for
+/// most types.
+///
+/// # Examples
+///
+/// ```no_run
+/// use plugy_core::guest::data;
+/// let visits: u32 = data().unwrap_or_default();
+/// ```
+pub fn data<T: serde::de::DeserializeOwned>() -> Result<T, ContextError> {
+    let blob = get_blob()?;
+    bincode::deserialize(&blob).map_err(|e| ContextError::Deserialize(e.to_string()))
+}
+
+/// Replaces the plugin's `Plugin::data` blob kept by the host.
+pub fn set_data<T: serde::ser::Serialize>(value: &T) -> Result<(), ContextError> {
+    let blob = bincode::serialize(value).map_err(|e| ContextError::Serialize(e.to_string()))?;
+    set_blob(blob)
+}
+
+/// Updates the plugin's `Plugin::data` blob in place, starting from
+/// `T::default()` while the blob is empty.
+///
+/// Calls into a plugin are serialized by the host, so nothing else touches the
+/// blob between the read and the write.
+///
+/// # Examples
+///
+/// ```no_run
+/// use plugy_core::guest::update_data;
+/// let visits = update_data(|visits: &mut u32| {
+///     *visits += 1;
+///     *visits
+/// })
+/// .unwrap();
+/// ```
+pub fn update_data<T, R>(f: impl FnOnce(&mut T) -> R) -> Result<R, ContextError>
+where
+    T: Default + serde::ser::Serialize + serde::de::DeserializeOwned,
+{
+    let blob = get_blob()?;
+    let mut value = if blob.is_empty() {
+        T::default()
+    } else {
+        bincode::deserialize(&blob).map_err(|e| ContextError::Deserialize(e.to_string()))?
+    };
+    let result = f(&mut value);
+    set_data(&value)?;
+    Ok(result)
+}
+
+/// The length of the metadata section encoding `records`, see
+/// [`metadata_section`].
+#[doc(hidden)]
+pub const fn metadata_len(records: &[(u8, &str)], list: u8) -> usize {
+    let mut len = 0;
+    let mut i = 0;
+    while i < records.len() {
+        let (kind, value) = records[i];
+        let value = value.as_bytes();
+        let mut start = 0;
+        while start < value.len() {
+            let end = record_end(value, start, kind == list);
+            if end > start {
+                len += 5 + end - start;
+            }
+            start = end + 1;
+        }
+        i += 1;
+    }
+    len
+}
+
+/// Encodes `records` into the section `#[plugin_metadata]` embeds, in a const
+/// context so that the package's details can come from `env!`.
+///
+/// Each record is its kind, the length of its value as a little endian `u32`
+/// and the value. Empty values are left out, and values of kind `list` hold
+/// several records separated by `:`, the way Cargo lists the authors.
+#[doc(hidden)]
+pub const fn metadata_section<const N: usize>(records: &[(u8, &str)], list: u8) -> [u8; N] {
+    let mut section = [0u8; N];
+    let mut at = 0;
+    let mut i = 0;
+    while i < records.len() {
+        let (kind, value) = records[i];
+        let value = value.as_bytes();
+        let mut start = 0;
+        while start < value.len() {
+            let end = record_end(value, start, kind == list);
+            if end > start {
+                section[at] = kind;
+                let len = ((end - start) as u32).to_le_bytes();
+                let mut j = 0;
+                while j < 4 {
+                    section[at + 1 + j] = len[j];
+                    j += 1;
+                }
+                at += 5;
+                let mut j = start;
+                while j < end {
+                    section[at] = value[j];
+                    at += 1;
+                    j += 1;
+                }
+            }
+            start = end + 1;
+        }
+        i += 1;
+    }
+    section
+}
+
+/// Where the record of `value` starting at `start` ends.
+const fn record_end(value: &[u8], start: usize, list: bool) -> usize {
+    let mut end = start;
+    while end < value.len() && !(list && value[end] == b':') {
+        end += 1;
+    }
+    end
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug, Default, PartialEq, serde::Deserialize)]
+    struct Counter {
+        count: u32,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct Config {
+        start: u32,
+    }
+
+    impl From<Config> for Counter {
+        fn from(config: Config) -> Self {
+            Counter {
+                count: config.start,
+            }
+        }
+    }
+
+    #[test]
+    fn constructs_instances_at_load() {
+        // Loaded without a config, a plugin with fields starts from `Default`
+        set_init(Vec::new());
+        assert_eq!(construct_by(build_instance::<Counter>), Ok(()));
+        with_instance(|counter: &mut Counter| counter.count += 1);
+        assert_eq!(with_instance(|counter: &mut Counter| counter.count), 1);
+
+        set_init(Vec::new());
+        assert!(construct_by(build_configured_instance::<Config, Counter>).is_err());
+
+        set_init(bincode::serialize(&7u32).unwrap());
+        assert_eq!(
+            construct_by(build_configured_instance::<Config, Counter>),
+            Ok(())
+        );
+        assert_eq!(with_instance(|counter: &mut Counter| counter.count), 7);
+    }
+
+    #[test]
+    fn encodes_metadata_in_const_contexts() {
+        const RECORDS: &[(u8, &str)] = &[(1, "greeter"), (4, ""), (3, "Ann:Bob")];
+        const SECTION: [u8; metadata_len(RECORDS, 3)] = metadata_section(RECORDS, 3);
+        assert_eq!(
+            SECTION,
+            *b"\x01\x07\0\0\0greeter\x03\x03\0\0\0Ann\x03\x03\0\0\0Bob"
+        );
+    }
+}
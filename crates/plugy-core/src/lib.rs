@@ -9,12 +9,25 @@
 //!
 //! - [`bitwise`](bitwise/index.html): A module providing utilities for working with bitwise operations and conversions.
 //! - [`guest`](guest/index.html): A module that facilitates communication between the host application and Wasm plugins.
+//! - [`error`](error/index.html): Errors shared between the host and the guest.
 //!
 
 use std::{future::Future, pin::Pin};
 pub mod bitwise;
+pub mod error;
 pub mod guest;
 
+/// The version of the ABI between hosts and guests, bumped whenever a change
+/// of how they exchange calls breaks plugins built against earlier versions.
+///
+/// Guests export it as `_plugy_abi_version`, which the runtime checks when
+/// loading them.
+pub const ABI_VERSION: u32 = 1;
+
+/// Lets the code generated by `#[plugin]` name serde through plugy.
+#[doc(hidden)]
+pub use serde;
+
 /// A trait for loading plugin module data asynchronously.
 ///
 /// This trait defines the behavior for loading plugin module data asynchronously.
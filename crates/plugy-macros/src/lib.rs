@@ -5,13 +5,225 @@
 //! working with plugins written in WebAssembly (Wasm) within your Rust applications.
 //!
 use convert_case::{Case, Casing};
+use darling::{ast::NestedMeta, FromMeta};
 use proc_macro::TokenStream;
 use proc_macro2::{Ident, Span};
 use quote::{quote, ToTokens};
+use std::collections::HashMap;
 use syn::{
     parse_macro_input, DeriveInput, FnArg, ImplItem, ImplItemFn, ItemImpl, ItemTrait, MetaNameValue,
 };
 
+/// The arguments of `#[plugin]`.
+#[derive(Default, FromMeta)]
+struct PluginArgs {
+    /// The host side types of the trait's associated types.
+    #[darling(default)]
+    types: HashMap<Ident, TypeArg>,
+    /// The instantiations of a generic trait.
+    #[darling(default)]
+    instances: Instances,
+}
+
+/// A type given as a macro argument, either as a path or, for types that are
+/// not valid expressions such as `Vec<u8>`, as a string. Arguments read with
+/// [`parse_type_args`] take such types verbatim too.
+struct TypeArg(syn::Type);
+
+impl FromMeta for TypeArg {
+    fn from_expr(expr: &syn::Expr) -> darling::Result<Self> {
+        match expr {
+            syn::Expr::Path(path) => Ok(TypeArg(syn::Type::Path(syn::TypePath {
+                qself: path.qself.clone(),
+                path: path.path.clone(),
+            }))),
+            syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(ty),
+                ..
+            }) => ty.parse().map(TypeArg).map_err(darling::Error::from),
+            syn::Expr::Verbatim(ty) => syn::parse2(ty.clone())
+                .map(TypeArg)
+                .map_err(darling::Error::from),
+            _ => Err(darling::Error::unexpected_expr_type(expr)),
+        }
+        .map_err(|e| e.with_span(expr))
+    }
+}
+
+/// Splits the arguments of a macro like `NestedMeta::parse_meta_list`, reading
+/// the value of the `keys` as a type, so that `data = Plugin<Addr>` needs no
+/// quotes. The type is handed to darling as a verbatim expression.
+fn parse_type_args(args: proc_macro2::TokenStream, keys: &[&str]) -> syn::Result<Vec<NestedMeta>> {
+    use syn::parse::{ParseStream, Parser};
+    let parser = |input: ParseStream| {
+        let mut items = Vec::new();
+        while !input.is_empty() {
+            let is_type = input.peek2(syn::Token![=])
+                && !input.peek3(syn::LitStr)
+                && input
+                    .fork()
+                    .parse::<Ident>()
+                    .is_ok_and(|key| keys.iter().any(|k| key == k));
+            items.push(if is_type {
+                NestedMeta::Meta(syn::Meta::NameValue(MetaNameValue {
+                    path: input.parse()?,
+                    eq_token: input.parse()?,
+                    value: syn::Expr::Verbatim(input.parse::<syn::Type>()?.into_token_stream()),
+                }))
+            } else {
+                input.parse()?
+            });
+            if input.is_empty() {
+                break;
+            }
+            input.parse::<syn::Token![,]>()?;
+        }
+        Ok(items)
+    };
+    parser.parse2(args)
+}
+
+/// The instantiations of a generic plugin trait, given as trait paths such as
+/// `instances(Codec<u32>, Codec<String>)`.
+#[derive(Default)]
+struct Instances(Vec<syn::Path>);
+
+impl FromMeta for Instances {
+    fn from_meta(item: &syn::Meta) -> darling::Result<Self> {
+        match item {
+            syn::Meta::List(list) => list
+                .parse_args_with(
+                    syn::punctuated::Punctuated::<syn::Path, syn::Token![,]>::parse_terminated,
+                )
+                .map(|paths| Instances(paths.into_iter().collect()))
+                .map_err(darling::Error::from),
+            _ => Err(
+                darling::Error::unsupported_format("expected a list of trait instances")
+                    .with_span(item),
+            ),
+        }
+    }
+}
+
+/// An instantiation of a plugin trait, which non-generic traits have exactly
+/// one of.
+struct Instance {
+    /// The name the instantiation is exported under, e.g. `Codec<u32>`.
+    name: String,
+    /// The types bound to the trait's type parameters.
+    args: Vec<syn::Type>,
+}
+
+/// The type arguments of a path segment, leaving out associated type bindings.
+fn type_args(segment: &syn::PathSegment) -> Vec<syn::Type> {
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) => args
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                syn::GenericArgument::Type(ty) => Some(ty.clone()),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The name the instantiation of a plugin trait named by `segment` is exported
+/// under. Type arguments are kept as written, so host and guest have to spell
+/// them the same way.
+fn trait_instance(segment: &syn::PathSegment) -> String {
+    let args: Vec<String> = type_args(segment)
+        .iter()
+        .map(|arg| arg.to_token_stream().to_string().replace(' ', ""))
+        .collect();
+    if args.is_empty() {
+        segment.ident.to_string()
+    } else {
+        format!("{}<{}>", segment.ident, args.join(","))
+    }
+}
+
+/// An identifier for the generated function exported as `name`.
+fn export_ident(name: &str) -> Ident {
+    let name = name.replace(|c: char| !c.is_ascii_alphanumeric() && c != '_', "_");
+    Ident::new(&name, Span::call_site())
+}
+
+/// Checks the declared instantiations of `trait_item` against its parameters.
+fn trait_instances(trait_item: &ItemTrait, paths: &[syn::Path]) -> syn::Result<Vec<Instance>> {
+    let trait_name = &trait_item.ident;
+    if let Some(param) = trait_item
+        .generics
+        .params
+        .iter()
+        .find(|param| !matches!(param, syn::GenericParam::Type(_)))
+    {
+        return Err(syn::Error::new_spanned(
+            param,
+            "plugin traits can only be generic over types",
+        ));
+    }
+    let params: Vec<String> = trait_item
+        .generics
+        .type_params()
+        .map(|param| param.ident.to_string())
+        .collect();
+    if params.is_empty() {
+        if let Some(path) = paths.first() {
+            return Err(syn::Error::new_spanned(
+                path,
+                "only generic plugin traits declare instances",
+            ));
+        }
+        return Ok(vec![Instance {
+            name: trait_name.to_string(),
+            args: Vec::new(),
+        }]);
+    }
+    if paths.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &trait_item.generics,
+            format!(
+                "generic plugin traits declare the types they are used with, \
+                 as in `#[plugin(instances({trait_name}<u32>))]`"
+            ),
+        ));
+    }
+    let params: Vec<&str> = params.iter().map(String::as_str).collect();
+    if let Some(supertrait) =
+        plugin_supertraits(trait_item).find(|path| mentions(&path.to_token_stream(), &params))
+    {
+        return Err(syn::Error::new_spanned(
+            supertrait,
+            "supertraits of generic plugin traits cannot depend on their parameters",
+        ));
+    }
+    paths
+        .iter()
+        .map(|path| {
+            let segment = path
+                .segments
+                .last()
+                .filter(|segment| segment.ident == *trait_name)
+                .ok_or_else(|| {
+                    syn::Error::new_spanned(path, format!("expected an instance of `{trait_name}`"))
+                })?;
+            let args = type_args(segment);
+            if args.len() != params.len() {
+                return Err(syn::Error::new_spanned(
+                    segment,
+                    format!("expected {} type argument(s)", params.len()),
+                ));
+            }
+            Ok(Instance {
+                name: trait_instance(segment),
+                args,
+            })
+        })
+        .collect()
+}
+
 /// A procedural macro attribute for generating an asynchronous and callable version of a trait on the host side.
 ///
 /// This procedural macro generates an asynchronous version of the provided trait by
@@ -21,8 +233,35 @@ use syn::{
 ///
 /// # Arguments
 ///
-/// This macro takes no arguments directly. It operates on the trait provided in the
-/// input token stream.
+/// - `types(Name = Type, ...)`: the host side types of the trait's associated
+///   types, which wrappers then use in place of `Self::Name`. Associated types
+///   left out are exchanged as raw bytes, `Vec<u8>`. Types that are not plain
+///   paths are given as strings, as in `types(Output = "Vec<String>")`.
+/// - `instances(Trait<A>, Trait<B>, ...)`: the instantiations of a generic
+///   trait plugins implement. Each is exported under its own name, such as
+///   `Codec<u32>::encode`, and the wrapper takes the trait's parameters first,
+///   as in `CodecWrapper<u32, P, D>`. Guests implement them with
+///   `#[plugin_impl]` on `impl Codec<u32> for MyPlugin`, spelling the types as
+///   they are declared here.
+///
+/// Supertraits must be plugin traits too, apart from std traits such as `Send`,
+/// `PartialEq` or `std::fmt::Display`.
+/// Their methods are available on the wrapper through the generated
+/// `{Supertrait}WrapperExt` traits, and supertraits of supertraits have to be
+/// listed as well to be reachable. On the guest, the dispatcher of the trait
+/// reaches its supertraits' methods, so their impls need no `#[plugin_impl]`.
+///
+/// Methods returning `Self`, even nested as in `(Self, u8)`, have no wrapper,
+/// since the host has no plugin type to decode it into.
+///
+/// Functions without a receiver, such as `fn version() -> String`, are exported
+/// too. They get a `where Self: Sized` bound so the trait stays usable as
+/// `dyn Trait`, and are called through the wrapper like any other method.
+///
+/// The argument and return types of every method are hashed on both sides, and
+/// wrappers refuse to call a plugin built against another signature of the
+/// method with `PluginError::SignatureMismatch` rather than letting the guest
+/// misread its arguments.
 ///
 /// # Examples
 ///
@@ -30,12 +269,51 @@ use syn::{
 /// #[plugy_macros::plugin]
 /// pub trait MyTrait {
 ///     fn sync_method(&self, param: u32) -> u32;
+///     fn version() -> String;
 /// }
 /// ```
 #[proc_macro_attribute]
-pub fn plugin(_: TokenStream, input: TokenStream) -> TokenStream {
-    let original_trait = parse_macro_input!(input as ItemTrait);
-    let async_trait = generate_async_trait(&original_trait);
+pub fn plugin(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = match NestedMeta::parse_meta_list(args.into())
+        .map_err(darling::Error::from)
+        .and_then(|args| PluginArgs::from_list(&args))
+    {
+        Ok(args) => args,
+        Err(e) => return e.write_errors().into(),
+    };
+    let types = args
+        .types
+        .into_iter()
+        .map(|(name, ty)| (name.to_string(), ty.0.into_token_stream()))
+        .collect();
+    let mut original_trait = parse_macro_input!(input as ItemTrait);
+    let instances = match trait_instances(&original_trait, &args.instances.0) {
+        Ok(instances) => instances,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let sigs = original_trait.items.iter().filter_map(|item| match item {
+        syn::TraitItem::Fn(method) => Some(&method.sig),
+        _ => None,
+    });
+    if let Err(e) = check_plugin_fns(sigs) {
+        return e.to_compile_error().into();
+    }
+    // Functions without a receiver would make the trait unusable as `dyn Trait`
+    for item in &mut original_trait.items {
+        if let syn::TraitItem::Fn(method) = item {
+            if method.sig.receiver().is_none() {
+                method
+                    .sig
+                    .generics
+                    .make_where_clause()
+                    .predicates
+                    .push(syn::parse_quote!(Self: Sized));
+            }
+        }
+    }
+    let async_trait = generate_async_trait(&original_trait, &types, &instances);
+    let dispatch = generate_dispatch(&original_trait);
+    original_trait.items.extend(dispatch);
 
     let output = quote! {
         #original_trait
@@ -45,89 +323,559 @@ pub fn plugin(_: TokenStream, input: TokenStream) -> TokenStream {
     output.into()
 }
 
-fn generate_async_trait(trait_item: &ItemTrait) -> proc_macro2::TokenStream {
-    let trait_name = &trait_item.ident;
-    let trait_methods = &trait_item.items;
-
-    let mut generic_types = vec![];
-
-    let async_methods = trait_methods.iter().map(|item| match item {
-        syn::TraitItem::Fn(method) => {
-            let method_name = &method.sig.ident;
-            let method_inputs: Vec<_> = method
-                .sig
-                .inputs
-                .iter()
-                .map(|input| match &input {
-                    FnArg::Receiver(_) => input.to_token_stream(),
-                    FnArg::Typed(typed) => match *typed.ty.clone() {
-                        syn::Type::Path(path) => {
-                            if path.path.segments.iter().any(|seg| {
-                                seg.ident == "Self"
-                                    || seg.arguments.to_token_stream().to_string().contains("Self")
-                            }) {
-                                let arg_name = &typed.pat;
-                                quote! {
-                                    #arg_name: &Vec<u8>
-                                }
-                            } else {
-                                input.to_token_stream()
+/// Generates the hidden trait functions `#[plugin_impl]` builds the trait's
+/// dispatcher export from, so that methods relying on their default body can be
+/// called too, along with the list of signature hashes of all its methods. They
+/// take the name the trait is exported under, which tells the instantiations of
+/// a generic trait apart.
+///
+/// Methods that are generic, take `self` by value or mention `Self` in their
+/// signature are left out of the dispatcher; they are only callable when
+/// implemented explicitly.
+fn generate_dispatch(trait_item: &ItemTrait) -> Vec<syn::TraitItem> {
+    let mut names = Vec::new();
+    let mut arms = Vec::new();
+    let mut signatures = Vec::new();
+    for item in &trait_item.items {
+        let syn::TraitItem::Fn(method) = item else {
+            continue;
+        };
+        let sig = &method.sig;
+        let signature = signature_hash(sig);
+        let method_name = sig.ident.to_string();
+        signatures.push(quote! { (#method_name, #signature) });
+        let by_value = sig.receiver().is_some_and(|r| r.reference.is_none());
+        let mentions_self = sig
+            .inputs
+            .iter()
+            .filter_map(|arg| match arg {
+                FnArg::Typed(t) => Some(t.ty.to_token_stream()),
+                FnArg::Receiver(_) => None,
+            })
+            .chain(std::iter::once(sig.output.to_token_stream()))
+            .any(|ty| mentions(&ty, &["Self"]));
+        if !sig.generics.params.is_empty() || by_value || mentions_self {
+            continue;
+        }
+        let method_name = &sig.ident;
+        let name = method_name.to_string();
+        let GuestArgs {
+            bindings,
+            types,
+            values,
+        } = guest_args(&sig.inputs);
+        let call = match sig.receiver() {
+            Some(_) => quote! { self.#method_name(#(#values),*) },
+            None => quote! { Self::#method_name(#(#values),*) },
+        };
+        arms.push(quote! {
+            Some(#name) => {
+                let (#(#bindings),*): (#(#types),*) = call.args();
+                Some(plugy::core::guest::write_msg(&#call))
+            }
+        });
+        names.push(name);
+    }
+    let supertraits: Vec<_> = plugin_supertraits(trait_item).collect();
+    let super_names = supertraits
+        .iter()
+        .map(|path| path.segments.last().map(trait_instance).unwrap_or_default());
+    let super_names: Vec<_> = super_names.collect();
+    // The arguments and outputs of a generic trait's methods cross the wire
+    let params = trait_item.generics.type_params().map(|param| &param.ident);
+    vec![
+        syn::parse_quote! {
+            #[doc(hidden)]
+            #[cfg(target_arch = "wasm32")]
+            fn __plugy_methods(instance: &str) -> Vec<String>
+            where
+                Self: Sized,
+            {
+                let names: &[&str] = &[#(#names),*];
+                let mut methods: Vec<String> = names
+                    .iter()
+                    .map(|method| format!("{instance}::{method}"))
+                    .collect();
+                #(methods.extend(<Self as #supertraits>::__plugy_methods(#super_names));)*
+                methods
+            }
+        },
+        syn::parse_quote! {
+            #[doc(hidden)]
+            #[cfg(target_arch = "wasm32")]
+            fn __plugy_signatures(instance: &str) -> Vec<(String, u64)>
+            where
+                Self: Sized,
+            {
+                let signatures: &[(&str, u64)] = &[#(#signatures),*];
+                let mut signatures: Vec<(String, u64)> = signatures
+                    .iter()
+                    .map(|(method, signature)| (format!("{instance}::{method}"), *signature))
+                    .collect();
+                #(signatures.extend(<Self as #supertraits>::__plugy_signatures(#super_names));)*
+                signatures
+            }
+        },
+        syn::parse_quote! {
+            #[doc(hidden)]
+            #[cfg(target_arch = "wasm32")]
+            fn __plugy_dispatch(
+                &mut self,
+                instance: &str,
+                call: &plugy::core::guest::Dispatch,
+            ) -> Option<u64>
+            where
+                Self: Sized,
+                #(#params: plugy::core::serde::Serialize + plugy::core::serde::de::DeserializeOwned,)*
+            {
+                let method = call
+                    .method()
+                    .strip_prefix(instance)
+                    .and_then(|method| method.strip_prefix("::"));
+                match method {
+                    #(#arms)*
+                    _ => {
+                        #(
+                            if let Some(output) = <Self as #supertraits>::__plugy_dispatch(self, #super_names, call) {
+                                return Some(output);
                             }
-                        }
-                        _ => input.to_token_stream(),
-                    },
-                })
-                .collect();
-            let method_output = &method.sig.output;
-            let method_name_str = method_name.to_string();
-            let values: Vec<_> = method
-                .sig
-                .inputs
-                .iter()
-                .filter_map(|arg| match arg {
-                    syn::FnArg::Receiver(_) => None,
-                    syn::FnArg::Typed(t) => Some(t.pat.to_token_stream()),
-                })
-                .collect();
-            quote! {
-                pub async fn #method_name(#(#method_inputs), *) #method_output {
-                    let func = self.handle.get_func(#method_name_str).await.unwrap();
-                    func.call_unchecked(&(#(#values),*)).await
+                        )*
+                        None
+                    }
                 }
             }
+        },
+    ]
+}
+
+/// The extension trait carrying the wrapper methods of the plugin trait `ident`.
+fn wrapper_ext(ident: &Ident) -> Ident {
+    Ident::new(&format!("{ident}WrapperExt"), ident.span())
+}
+
+/// The supertraits of a plugin trait that are plugin traits themselves, which
+/// is assumed of all but the std traits, named by path or by their usual name.
+///
+/// Any other supertrait is expected to have a `{Trait}WrapperExt`, so one that
+/// is not a plugin trait fails to resolve at its own span.
+fn plugin_supertraits(trait_item: &ItemTrait) -> impl Iterator<Item = &syn::Path> {
+    const STD_CRATES: &[&str] = &["std", "core", "alloc"];
+    const STD_TRAITS: &[&str] = &[
+        "Send",
+        "Sync",
+        "Sized",
+        "Unpin",
+        "Copy",
+        "Clone",
+        "Debug",
+        "Default",
+        "Any",
+        "PartialEq",
+        "Eq",
+        "PartialOrd",
+        "Ord",
+        "Hash",
+        "Display",
+        "Error",
+        "ToString",
+        "From",
+        "Into",
+        "TryFrom",
+        "TryInto",
+        "AsRef",
+        "AsMut",
+        "Borrow",
+        "BorrowMut",
+        "Deref",
+        "DerefMut",
+        "Iterator",
+        "IntoIterator",
+        "Extend",
+        "FromIterator",
+        "Fn",
+        "FnMut",
+        "FnOnce",
+        "FromStr",
+        "Read",
+        "Write",
+    ];
+    trait_item
+        .supertraits
+        .iter()
+        .filter_map(|bound| match bound {
+            syn::TypeParamBound::Trait(bound)
+                if matches!(bound.modifier, syn::TraitBoundModifier::None) =>
+            {
+                Some(&bound.path)
+            }
+            _ => None,
+        })
+        .filter(|path| {
+            let in_std = path.segments.len() > 1
+                && path
+                    .segments
+                    .first()
+                    .is_some_and(|first| STD_CRATES.contains(&first.ident.to_string().as_str()));
+            !in_std
+                && path
+                    .segments
+                    .last()
+                    .is_some_and(|last| !STD_TRAITS.contains(&last.ident.to_string().as_str()))
+        })
+}
+
+/// Replaces every `Self::Name` in `tokens` with the type bound to `Name` on the
+/// host, falling back to raw bytes for unbound associated types, and every
+/// type parameter in `params` with its argument.
+fn bind_types(
+    tokens: proc_macro2::TokenStream,
+    types: &HashMap<String, proc_macro2::TokenStream>,
+    params: &HashMap<String, proc_macro2::TokenStream>,
+) -> proc_macro2::TokenStream {
+    use proc_macro2::{Group, TokenTree};
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let mut bound = proc_macro2::TokenStream::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let in_path = i
+            .checked_sub(1)
+            .is_some_and(|prev| matches!(&tokens[prev], TokenTree::Punct(p) if p.as_char() == ':'));
+        match &tokens[i..] {
+            [TokenTree::Ident(this), TokenTree::Punct(a), TokenTree::Punct(b), TokenTree::Ident(name), ..]
+                if this == "Self" && a.as_char() == ':' && b.as_char() == ':' =>
+            {
+                bound.extend(
+                    types
+                        .get(&name.to_string())
+                        .cloned()
+                        .unwrap_or_else(|| quote! { Vec<u8> }),
+                );
+                i += 4;
+            }
+            [TokenTree::Ident(param), ..]
+                if !in_path && params.contains_key(&param.to_string()) =>
+            {
+                bound.extend(params[&param.to_string()].clone());
+                i += 1;
+            }
+            [TokenTree::Group(group), ..] => {
+                let mut inner =
+                    Group::new(group.delimiter(), bind_types(group.stream(), types, params));
+                inner.set_span(group.span());
+                bound.extend([TokenTree::Group(inner)]);
+                i += 1;
+            }
+            [token, ..] => {
+                bound.extend([token.clone()]);
+                i += 1;
+            }
+            [] => unreachable!(),
         }
-        syn::TraitItem::Type(ty) => {
-            let ident_type = &ty.ident;
+    }
+    bound
+}
 
-            generic_types.push(quote! {
-                #ident_type = Vec<u8>
-            });
-            quote! {}
+/// Whether `tokens` mention any of the identifiers `names`.
+fn mentions(tokens: &proc_macro2::TokenStream, names: &[&str]) -> bool {
+    tokens.clone().into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Ident(ident) => names.iter().any(|name| ident == name),
+        proc_macro2::TokenTree::Group(group) => mentions(&group.stream(), names),
+        _ => false,
+    })
+}
+
+/// The FNV-1a hash of the argument and return types of a plugin trait method,
+/// which the runtime compares between the host and the guest.
+///
+/// Paths are reduced to their last segment, so `String` and
+/// `std::string::String` hash alike. Associated types are hashed by name, as
+/// `Self::Name`, since the host and the guest bind them separately.
+fn signature_hash(sig: &syn::Signature) -> u64 {
+    let args: Vec<String> = sig
+        .inputs
+        .iter()
+        .filter_map(|arg| match arg {
+            FnArg::Typed(t) => Some(signature_type(t.ty.to_token_stream())),
+            FnArg::Receiver(_) => None,
+        })
+        .collect();
+    let output = match &sig.output {
+        syn::ReturnType::Type(_, ty) => signature_type(ty.to_token_stream()),
+        syn::ReturnType::Default => "()".to_owned(),
+    };
+    format!("({})->{output}", args.join(","))
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+/// The type `tokens` as hashed into a signature, with every path but
+/// `Self::Name` reduced to its last segment.
+fn signature_type(tokens: proc_macro2::TokenStream) -> String {
+    use proc_macro2::TokenTree;
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let mut normalized = String::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        match &tokens[i..] {
+            [TokenTree::Ident(segment), TokenTree::Punct(a), TokenTree::Punct(b), ..]
+                if segment != "Self" && a.as_char() == ':' && b.as_char() == ':' =>
+            {
+                i += 3;
+            }
+            [TokenTree::Punct(a), TokenTree::Punct(b), ..]
+                if a.as_char() == ':'
+                    && b.as_char() == ':'
+                    && !i.checked_sub(1).is_some_and(
+                        |prev| matches!(&tokens[prev], TokenTree::Ident(this) if this == "Self"),
+                    ) =>
+            {
+                i += 2;
+            }
+            [TokenTree::Group(group), ..] => {
+                let (open, close) = match group.delimiter() {
+                    proc_macro2::Delimiter::Parenthesis => ("(", ")"),
+                    proc_macro2::Delimiter::Bracket => ("[", "]"),
+                    proc_macro2::Delimiter::Brace => ("{", "}"),
+                    proc_macro2::Delimiter::None => ("", ""),
+                };
+                normalized.push_str(open);
+                normalized.push_str(&signature_type(group.stream()));
+                normalized.push_str(close);
+                i += 1;
+            }
+            [token, ..] => {
+                normalized.push_str(&token.to_string());
+                i += 1;
+            }
+            [] => unreachable!(),
         }
+    }
+    normalized
+}
 
-        _ => {
-            quote! {}
+/// A wrapper method calling `method` through `handle`, under the export name
+/// `name`. It returns the errors of the runtime, such as a signature mismatch
+/// or a quarantined plugin, alongside the method's output.
+fn wrapper_method(
+    method: &syn::TraitItemFn,
+    types: &HashMap<String, proc_macro2::TokenStream>,
+    params: &HashMap<String, proc_macro2::TokenStream>,
+    handle: proc_macro2::TokenStream,
+    name: proc_macro2::TokenStream,
+    bounds: Option<proc_macro2::TokenStream>,
+) -> proc_macro2::TokenStream {
+    let method_name = &method.sig.ident;
+    let method_inputs = method.sig.inputs.iter().map(|input| match &input {
+        // Calls are serialized by the runtime, so `&mut self`
+        // methods are exposed through a shared wrapper too
+        FnArg::Receiver(_) => quote! { &self },
+        FnArg::Typed(typed) => {
+            let arg_name = &typed.pat;
+            let ty = bind_types(typed.ty.to_token_stream(), types, params);
+            if mentions(&ty, &["Self"]) {
+                quote! { #arg_name: &Vec<u8> }
+            } else {
+                quote! { #arg_name: #ty }
+            }
         }
     });
+    // Functions without a receiver are called through the handle too
+    let receiver = method.sig.receiver().is_none().then(|| quote! { &self, });
+    let method_output = match &method.sig.output {
+        syn::ReturnType::Type(_, ty) => bind_types(ty.to_token_stream(), types, params),
+        syn::ReturnType::Default => quote! { () },
+    };
+    let values = method.sig.inputs.iter().filter_map(|arg| match arg {
+        syn::FnArg::Receiver(_) => None,
+        syn::FnArg::Typed(t) => Some(t.pat.to_token_stream()),
+    });
+    let signature = signature_hash(&method.sig);
+    quote! {
+        async fn #method_name(#receiver #(#method_inputs), *) -> anyhow::Result<#method_output> #bounds {
+            let func = #handle.get_func_with_signature(#name, #signature).await?;
+            func.call_checked(&(#(#values),*)).await
+        }
+    }
+}
+
+fn generate_async_trait(
+    trait_item: &ItemTrait,
+    types: &HashMap<String, proc_macro2::TokenStream>,
+    instances: &[Instance],
+) -> proc_macro2::TokenStream {
+    let trait_name = &trait_item.ident;
+    let params: Vec<&Ident> = trait_item
+        .generics
+        .type_params()
+        .map(|param| &param.ident)
+        .collect();
+    let generic = !params.is_empty();
+    // The host has no plugin type to decode a returned `Self` into
+    let methods: Vec<&syn::TraitItemFn> = trait_item
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            syn::TraitItem::Fn(method) => Some(method),
+            _ => None,
+        })
+        .filter(|method| {
+            let output = bind_types(method.sig.output.to_token_stream(), types, &HashMap::new());
+            !mentions(&output, &["Self"])
+        })
+        .collect();
+    let generic_types: Vec<_> = trait_item
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            syn::TraitItem::Type(ty) => {
+                let ident_type = &ty.ident;
+                let bound = types
+                    .get(&ident_type.to_string())
+                    .cloned()
+                    .unwrap_or_else(|| quote! { Vec<u8> });
+                Some(quote! { #ident_type = #bound })
+            }
+            _ => None,
+        })
+        .collect();
 
     let callable_trait_name = format!("{}Wrapper", trait_name);
     let callable_trait_ident = syn::Ident::new(&callable_trait_name, trait_name.span());
+    let ext_trait_ident = wrapper_ext(trait_name);
+    // Names the instantiations of a generic trait for the extension trait
+    let instance_trait = Ident::new(&format!("__Plugy{trait_name}Instance"), trait_name.span());
+
+    let ext_methods = methods.iter().map(|method| {
+        let method_name = method.sig.ident.to_string();
+        if !generic {
+            let name = format!("{trait_name}::{method_name}");
+            return wrapper_method(
+                method,
+                types,
+                &HashMap::new(),
+                quote! { self.plugin_handle() },
+                quote! { #name },
+                None,
+            );
+        }
+        wrapper_method(
+            method,
+            types,
+            &HashMap::new(),
+            quote! { self.plugin_handle() },
+            quote! { &format!("{}::{}", <(#(#params,)*) as #instance_trait>::NAME, #method_name) },
+            Some(quote! {
+                where
+                    (#(#params,)*): #instance_trait,
+                    #(#params: plugy::runtime::serde::Serialize + plugy::runtime::serde::de::DeserializeOwned,)*
+            }),
+        )
+    });
+
+    let instance_impls = instances.iter().map(|instance| {
+        let args = &instance.args;
+        let bound: HashMap<String, proc_macro2::TokenStream> = params
+            .iter()
+            .map(|param| param.to_string())
+            .zip(args.iter().map(ToTokens::to_token_stream))
+            .collect();
+        let async_methods = methods.iter().map(|method| {
+            let name = format!("{}::{}", instance.name, method.sig.ident);
+            let method = wrapper_method(
+                method,
+                types,
+                &bound,
+                quote! { self.handle },
+                quote! { #name },
+                None,
+            );
+            quote! { pub #method }
+        });
+        let name = &instance.name;
+        let named = generic.then(|| {
+            quote! {
+                #[cfg(not(target_arch = "wasm32"))]
+                impl #instance_trait for (#(#args,)*) {
+                    const NAME: &'static str = #name;
+                }
+            }
+        });
+        quote! {
+            #[cfg(not(target_arch = "wasm32"))]
+            impl<P, D: Clone + Send> #callable_trait_ident<#(#args,)* P, D> where D: 'static  {
+                #(#async_methods)*
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            impl<P, D: 'static> plugy::runtime::IntoCallable<P, D> for Box<dyn #trait_name<#(#args,)* #(#generic_types),*>> {
+                type Output = #callable_trait_ident<#(#args,)* P, D>;
+                fn into_callable(handle: plugy::runtime::PluginHandle<plugy::runtime::Plugin<D>>) -> Self::Output {
+                    #callable_trait_ident { handle, inner: std::marker::PhantomData }
+                }
+            }
+            #named
+        }
+    });
+    let instance_trait = generic.then(|| {
+        quote! {
+            #[doc(hidden)]
+            #[cfg(not(target_arch = "wasm32"))]
+            pub trait #instance_trait {
+                const NAME: &'static str;
+            }
+        }
+    });
+
+    let super_ext_impls = plugin_supertraits(trait_item).map(|path| {
+        let mut path = path.clone();
+        if let Some(last) = path.segments.last_mut() {
+            let args = type_args(last);
+            last.ident = wrapper_ext(&last.ident);
+            last.arguments = syn::PathArguments::AngleBracketed(syn::parse_quote!(<#(#args,)* D>));
+        }
+        quote! {
+            #[cfg(not(target_arch = "wasm32"))]
+            impl<#(#params,)* P, D: Clone + Send + 'static> #path for #callable_trait_ident<#(#params,)* P, D> {
+                fn plugin_handle(&self) -> &plugy::runtime::PluginHandle<plugy::runtime::Plugin<D>> {
+                    &self.handle
+                }
+            }
+        }
+    });
+    let phantom = if generic {
+        quote! { (#(#params,)* P) }
+    } else {
+        quote! { P }
+    };
 
     quote! {
         #[cfg(not(target_arch = "wasm32"))]
         #[derive(Debug, Clone)]
-        pub struct #callable_trait_ident<P, D> where D: 'static {
+        pub struct #callable_trait_ident<#(#params,)* P, D> where D: 'static {
             pub handle: plugy::runtime::PluginHandle<plugy::runtime::Plugin<D>>,
-            inner: std::marker::PhantomData<P>
+            inner: std::marker::PhantomData<#phantom>
         }
+        #(#instance_impls)*
+        #instance_trait
+        /// The methods of the plugin trait, for the wrappers of its subtraits.
         #[cfg(not(target_arch = "wasm32"))]
-        impl<P, D: Clone + Send> #callable_trait_ident<P, D> where D: 'static  {
-            #(#async_methods)*
+        #[allow(async_fn_in_trait)]
+        pub trait #ext_trait_ident<#(#params,)* D: Clone + Send + 'static> {
+            #[doc(hidden)]
+            fn plugin_handle(&self) -> &plugy::runtime::PluginHandle<plugy::runtime::Plugin<D>>;
+            #(#ext_methods)*
         }
         #[cfg(not(target_arch = "wasm32"))]
-        impl<P, D: 'static> plugy::runtime::IntoCallable<P, D> for Box<dyn #trait_name<#(#generic_types),*>> {
-            type Output = #callable_trait_ident<P, D>;
-            fn into_callable(handle: plugy::runtime::PluginHandle<plugy::runtime::Plugin<D>>) -> Self::Output {
+        impl<#(#params,)* P, D: Clone + Send + 'static> #ext_trait_ident<#(#params,)* D> for #callable_trait_ident<#(#params,)* P, D> {
+            fn plugin_handle(&self) -> &plugy::runtime::PluginHandle<plugy::runtime::Plugin<D>> {
+                &self.handle
+            }
+        }
+        #(#super_ext_impls)*
+        #[cfg(not(target_arch = "wasm32"))]
+        impl<#(#params,)* P, D: 'static> From<plugy::runtime::PluginHandle<plugy::runtime::Plugin<D>>> for #callable_trait_ident<#(#params,)* P, D> {
+            fn from(handle: plugy::runtime::PluginHandle<plugy::runtime::Plugin<D>>) -> Self {
                 #callable_trait_ident { handle, inner: std::marker::PhantomData }
             }
         }
@@ -144,6 +892,134 @@ fn impl_methods(imp: &ItemImpl) -> impl Iterator<Item = &ImplItemFn> {
         .filter(|_m| imp.trait_.is_some())
 }
 
+/// The arguments of a guest export, as read from the host and as handed to the
+/// plugin method.
+struct GuestArgs {
+    /// The patterns the deserialized arguments are bound to.
+    bindings: Vec<proc_macro2::TokenStream>,
+    /// The owned types the arguments are deserialized as.
+    types: Vec<proc_macro2::TokenStream>,
+    /// The expressions passed to the plugin method.
+    values: Vec<proc_macro2::TokenStream>,
+}
+
+/// Maps the arguments of a plugin method to owned transport types.
+///
+/// `&str` is read as a `String`, `&[T]` as a `Vec<T>` and `&T` or `&mut T` as
+/// a `T`, then borrowed again for the call. These serialize the same way, so
+/// the host side needs no mapping.
+fn guest_args(inputs: &syn::punctuated::Punctuated<FnArg, syn::token::Comma>) -> GuestArgs {
+    let mut args = GuestArgs {
+        bindings: Vec::new(),
+        types: Vec::new(),
+        values: Vec::new(),
+    };
+    for arg in inputs {
+        let syn::FnArg::Typed(t) = arg else {
+            continue;
+        };
+        let pat = &t.pat;
+        match &*t.ty {
+            syn::Type::Reference(reference) => {
+                let owned = match &*reference.elem {
+                    syn::Type::Path(path) if path.path.is_ident("str") => quote! { String },
+                    syn::Type::Slice(slice) => {
+                        let elem = &slice.elem;
+                        quote! { Vec<#elem> }
+                    }
+                    elem => elem.to_token_stream(),
+                };
+                args.types.push(owned);
+                if reference.mutability.is_some() {
+                    args.bindings.push(quote! { mut #pat });
+                    args.values.push(quote! { &mut #pat });
+                } else {
+                    args.bindings.push(pat.to_token_stream());
+                    args.values.push(quote! { &#pat });
+                }
+            }
+            ty => {
+                args.types.push(ty.to_token_stream());
+                args.bindings.push(pat.to_token_stream());
+                args.values.push(pat.to_token_stream());
+            }
+        }
+    }
+    args
+}
+
+/// The arguments of the guest side attributes.
+#[derive(Default, FromMeta)]
+struct ConfigArgs {
+    /// The config the plugin is constructed from, such as `config = Vec<u8>`.
+    #[darling(default)]
+    config: Option<TypeArg>,
+}
+
+/// Parses the optional `config = Type` argument of the guest side attributes.
+fn parse_config(args: TokenStream) -> darling::Result<Option<syn::Type>> {
+    let args = parse_type_args(args.into(), &["config"])?;
+    Ok(ConfigArgs::from_list(&args)?.config.map(|config| config.0))
+}
+
+/// The guest function handing the plugin instance to a closure, constructing
+/// it from `config` when one is given.
+fn instance_accessor(config: &Option<syn::Type>) -> proc_macro2::TokenStream {
+    match config {
+        Some(config) => quote! { plugy::core::guest::with_configured_instance::<#config, _, _> },
+        None => quote! { plugy::core::guest::with_instance },
+    }
+}
+
+/// The guest export constructing the instance of `ty` while the plugin loads,
+/// exported as `_plugy_construct_{name}`.
+fn instance_constructor(
+    config: &Option<syn::Type>,
+    ty: &syn::Type,
+    name: &str,
+) -> proc_macro2::TokenStream {
+    let construct = match config {
+        Some(config) => {
+            quote! { plugy::core::guest::construct_configured_instance::<#config, #ty>() }
+        }
+        None => quote! { plugy::core::guest::construct_instance::<#ty>() },
+    };
+    let export_name = format!("_plugy_construct_{name}");
+    let export = export_ident(&export_name);
+    quote! {
+        #[cfg(target_arch = "wasm32")]
+        #[export_name = #export_name]
+        pub extern "C" fn #export(_: u64) -> u64 {
+            #construct
+        }
+    }
+}
+
+/// Checks that plugin methods can be called across the wasm boundary: they
+/// borrow the plugin, if they take it at all, and are not generic.
+fn check_plugin_fns<'a>(sigs: impl Iterator<Item = &'a syn::Signature>) -> syn::Result<()> {
+    for sig in sigs {
+        if let Some(receiver) = sig.receiver().filter(|r| r.reference.is_none()) {
+            return Err(syn::Error::new_spanned(
+                receiver,
+                "plugin methods take `&self` or `&mut self`, the plugin is kept between calls",
+            ));
+        }
+        if let Some(param) = sig
+            .generics
+            .params
+            .iter()
+            .find(|param| !matches!(param, syn::GenericParam::Lifetime(_)))
+        {
+            return Err(syn::Error::new_spanned(
+                param,
+                "plugin methods cannot be generic, their types have to be known on both sides",
+            ));
+        }
+    }
+    Ok(())
+}
+
 /// A procedural macro for generating guest-side implementations of trait methods.
 ///
 /// This macro takes an implementation block for a trait and generates corresponding
@@ -155,6 +1031,29 @@ fn impl_methods(imp: &ItemImpl) -> impl Iterator<Item = &ImplItemFn> {
 /// functions that can be called from a guest environment to invoke methods on the
 /// trait implementation.
 ///
+/// Methods relying on their default body in the trait are reachable through a
+/// dispatcher exported for the trait, which requires the trait to be declared
+/// with `#[plugin]`.
+///
+/// Each method is exported as `_plugy_guest_{Trait}::{method}`, so a plugin may
+/// implement several plugin traits with methods of the same name. The host gets
+/// a wrapper for every trait from the same handle, e.g.
+/// `NamerWrapper::from(greeter.handle.clone())`. Instantiations of generic
+/// traits are exported under their type arguments, as in
+/// `_plugy_guest_Codec<u32>::encode`. The implemented trait is also recorded
+/// in the plugin's metadata, see `#[plugin_metadata]`.
+///
+/// The plugin type is constructed eagerly while the plugin loads, through the
+/// generated `_plugy_construct_{Trait}` export, so a plugin that cannot be
+/// constructed fails `Runtime::load` instead of its first call. Without a
+/// config it is built with `Default`, which the plugin type must implement
+/// along with `Deserialize`. That instance serves every following call until
+/// the plugin is restarted.
+/// With `#[plugin_impl(config = MyConfig)]` it is constructed through
+/// `From<MyConfig>` instead, from the config the host passed to
+/// `Runtime::load_with_config`. The config may be any type, such as
+/// `config = Vec<u8>` or `config = Config<T>`.
+///
 /// # Example
 ///
 /// ```rust,ignore
@@ -164,6 +1063,7 @@ fn impl_methods(imp: &ItemImpl) -> impl Iterator<Item = &ImplItemFn> {
 ///     fn greet(&self) -> String;
 /// }
 ///
+/// #[derive(Default, serde::Deserialize)]
 /// struct MyGreetPlugin;
 ///
 /// #[plugin_impl]
@@ -178,56 +1078,506 @@ fn impl_methods(imp: &ItemImpl) -> impl Iterator<Item = &ImplItemFn> {
 /// the `greet` method from the `Plugin` trait. The generated function can then be
 /// used to call the `greet` method from a host environment.
 #[proc_macro_attribute]
-pub fn plugin_impl(_metadata: TokenStream, input: TokenStream) -> TokenStream {
+pub fn plugin_impl(metadata: TokenStream, input: TokenStream) -> TokenStream {
+    let config = match parse_config(metadata) {
+        Ok(config) => config,
+        Err(e) => return e.write_errors().into(),
+    };
+    let with_instance = instance_accessor(&config);
     let cur_impl: proc_macro2::TokenStream = input.clone().into();
     let imp = parse_macro_input!(input as ItemImpl);
     let ty = &imp.self_ty;
+    if imp.trait_.is_none() {
+        return syn::Error::new_spanned(
+            ty,
+            "`#[plugin_impl]` applies to impls of plugin traits, \
+             expose hooks of inherent impls with `#[plugin_hooks]`",
+        )
+        .to_compile_error()
+        .into();
+    }
     let methods: Vec<&ImplItemFn> = impl_methods(&imp).collect();
+    if let Err(e) = check_plugin_fns(methods.iter().map(|m| &m.sig)) {
+        return e.to_compile_error().into();
+    }
+    if let Some(param) = imp.generics.type_params().next() {
+        return syn::Error::new_spanned(
+            param,
+            "`#[plugin_impl]` needs a concrete instantiation of the trait",
+        )
+        .to_compile_error()
+        .into();
+    }
+    let trait_name = imp
+        .trait_
+        .as_ref()
+        .and_then(|(_, path, _)| path.segments.last())
+        .map(trait_instance)
+        .unwrap_or_default();
     let derived: proc_macro2::TokenStream = methods
         .iter()
         .map(|m| {
             let method_name = &m.sig.ident;
-            let args = &m.sig.inputs;
-            let types: Vec<_> = args
-                .iter()
-                .filter_map(|arg| match arg {
-                    syn::FnArg::Receiver(_) => None,
-                    syn::FnArg::Typed(t) => Some(t.ty.to_token_stream()),
-                })
-                .collect();
-            let values: Vec<_> = args
-                .iter()
-                .filter_map(|arg| match arg {
-                    syn::FnArg::Receiver(_) => None,
-                    syn::FnArg::Typed(t) => Some(t.pat.to_token_stream()),
-                })
-                .collect();
-            let expose_name = format!("_plugy_guest_{}", method_name);
-            let expose_name_ident = syn::Ident::new(&expose_name, Span::call_site());
+            let GuestArgs {
+                bindings,
+                types,
+                values,
+            } = guest_args(&m.sig.inputs);
+            let expose_name = format!("_plugy_guest_{trait_name}::{method_name}");
+            let expose_name_ident = export_ident(&format!("_plugy_guest_{trait_name}_{method_name}"));
+            let trait_path = imp.trait_.as_ref().map(|(_, path, _)| path);
+            if m.sig.receiver().is_none() {
+                // Associated functions need no plugin instance
+                return quote! {
+                    #[export_name = #expose_name]
+                    pub unsafe extern "C" fn #expose_name_ident(value: u64) -> u64 {
+                        let (#(#bindings),*): (#(#types),*) = plugy::core::guest::read_msg(value);
+                        plugy::core::guest::write_msg(&<#ty as #trait_path>::#method_name(#(#values),*))
+                    }
+                };
+            }
             quote! {
-                #[no_mangle]
+                #[export_name = #expose_name]
                 pub unsafe extern "C" fn #expose_name_ident(value: u64) -> u64 {
-                    let (value, #(#values),*): (#ty, #(#types),*)  = plugy::core::guest::read_msg(value);
-                    plugy::core::guest::write_msg(&value.#method_name(#(#values),*))
+                    let (#(#bindings),*): (#(#types),*) = plugy::core::guest::read_msg(value);
+                    #with_instance(|__plugy_self: &mut #ty| {
+                        // Qualified, as the plugin may implement several
+                        // instantiations of a generic trait
+                        plugy::core::guest::write_msg(&<#ty as #trait_path>::#method_name(__plugy_self, #(#values),*))
+                    })
                 }
             }
         })
         .collect();
 
+    let dispatch = imp.trait_.as_ref().map(|(_, trait_path, _)| {
+        let dispatch_name = format!("_plugy_dispatch_{trait_name}");
+        let dispatch_ident = export_ident(&dispatch_name);
+        let methods_name = format!("_plugy_methods_{trait_name}");
+        let methods_ident = export_ident(&methods_name);
+        let signatures_name = format!("_plugy_signatures_{trait_name}");
+        let signatures_ident = export_ident(&signatures_name);
+        quote! {
+            #[cfg(target_arch = "wasm32")]
+            #[export_name = #dispatch_name]
+            pub unsafe extern "C" fn #dispatch_ident(value: u64) -> u64 {
+                let call = plugy::core::guest::Dispatch::read(value);
+                #with_instance(|value: &mut #ty| <#ty as #trait_path>::__plugy_dispatch(value, #trait_name, &call))
+                    .unwrap_or_else(|| panic!("no method `{}` to dispatch", call.method()))
+            }
+            #[cfg(target_arch = "wasm32")]
+            #[export_name = #methods_name]
+            pub unsafe extern "C" fn #methods_ident(_: u64) -> u64 {
+                plugy::core::guest::write_msg(&<#ty as #trait_path>::__plugy_methods(#trait_name))
+            }
+            #[cfg(target_arch = "wasm32")]
+            #[export_name = #signatures_name]
+            pub unsafe extern "C" fn #signatures_ident(_: u64) -> u64 {
+                plugy::core::guest::write_msg(&<#ty as #trait_path>::__plugy_signatures(#trait_name))
+            }
+        }
+    });
+
+    let metadata = imp
+        .trait_
+        .is_some()
+        .then(|| metadata_section(&[(METADATA_TRAIT, trait_name.clone())]));
+    let constructor = instance_constructor(&config, ty, &trait_name);
+
     quote! {
         #cur_impl
+        #constructor
         #derived
+        #dispatch
+        #metadata
+    }
+    .into()
+}
+
+/// The custom section plugin metadata is embedded in, along with the kinds of
+/// its records, which `plugy_runtime::PluginMetadata` reads back.
+const METADATA_SECTION: &str = "plugy_metadata";
+const METADATA_NAME: u8 = 1;
+const METADATA_VERSION: u8 = 2;
+const METADATA_AUTHOR: u8 = 3;
+const METADATA_DESCRIPTION: u8 = 4;
+const METADATA_TRAIT: u8 = 5;
+
+/// A static placing `records` in the metadata section of the guest, where the
+/// linker concatenates them with the records of other statics.
+fn metadata_section(records: &[(u8, String)]) -> proc_macro2::TokenStream {
+    let mut bytes = Vec::new();
+    for (kind, value) in records {
+        bytes.push(*kind);
+        bytes.extend((value.len() as u32).to_le_bytes());
+        bytes.extend(value.as_bytes());
+    }
+    let len = bytes.len();
+    quote! {
+        #[cfg(target_arch = "wasm32")]
+        const _: () = {
+            #[used]
+            #[link_section = #METADATA_SECTION]
+            static METADATA: [u8; #len] = [#(#bytes),*];
+        };
+    }
+}
+
+/// The arguments of `#[plugin_metadata]`.
+#[derive(Default, FromMeta)]
+struct MetadataArgs {
+    name: Option<String>,
+    version: Option<String>,
+    authors: Option<Vec<syn::LitStr>>,
+    description: Option<String>,
+}
+
+/// A procedural macro embedding metadata about the plugin in the wasm module.
+///
+/// Apply it to the plugin type. Hosts read the metadata back without running
+/// the plugin through `PluginMetadata::from_bytes` or `Runtime::inspect`,
+/// along with the traits implemented with `#[plugin_impl]`.
+///
+/// # Arguments
+///
+/// All optional, defaulting to the package's as given in `Cargo.toml`:
+///
+/// - `name = "..."`
+/// - `version = "..."`
+/// - `authors("...", ...)`
+/// - `description = "..."`
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use plugy_macros::plugin_metadata;
+///
+/// #[plugin_metadata(name = "greeter", description = "Greets people")]
+/// #[derive(Default, serde::Deserialize)]
+/// struct MyGreetPlugin;
+/// ```
+#[proc_macro_attribute]
+pub fn plugin_metadata(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = match NestedMeta::parse_meta_list(args.into())
+        .map_err(darling::Error::from)
+        .and_then(|args| MetadataArgs::from_list(&args))
+    {
+        Ok(args) => args,
+        Err(e) => return e.write_errors().into(),
+    };
+    // Left out, the details of the package are read where the plugin is
+    // compiled, as `env!` in the generated code
+    let value = |value: Option<String>, key: &str| match value {
+        Some(value) => quote! { #value },
+        None => quote! { env!(#key) },
+    };
+    let name = value(args.name, "CARGO_PKG_NAME");
+    let version = value(args.version, "CARGO_PKG_VERSION");
+    let description = value(args.description, "CARGO_PKG_DESCRIPTION");
+    let authors = match args.authors {
+        Some(authors) => authors
+            .iter()
+            .map(|author| quote! { (#METADATA_AUTHOR, #author) })
+            .collect(),
+        None => vec![quote! { (#METADATA_AUTHOR, env!("CARGO_PKG_AUTHORS")) }],
+    };
+    let input = proc_macro2::TokenStream::from(input);
+    quote! {
+        #input
+        #[cfg(target_arch = "wasm32")]
+        const _: () = {
+            const RECORDS: &[(u8, &str)] = &[
+                (#METADATA_NAME, #name),
+                (#METADATA_VERSION, #version),
+                (#METADATA_DESCRIPTION, #description),
+                #(#authors,)*
+            ];
+            #[used]
+            #[link_section = #METADATA_SECTION]
+            static METADATA: [u8; plugy::core::guest::metadata_len(RECORDS, #METADATA_AUTHOR)] =
+                plugy::core::guest::metadata_section(RECORDS, #METADATA_AUTHOR);
+        };
     }
     .into()
 }
 
+/// A procedural macro exposing lifecycle hooks of a plugin to the runtime.
+///
+/// Apply it to an inherent impl block of the plugin type. The recognized hooks
+/// are all optional:
+///
+/// - `on_load(&self)` runs when the plugin is loaded, an error fails the load,
+///   and again on every restart of its instance.
+/// - `on_unload(&self)` runs before the plugin is unloaded or the runtime shut down.
+/// - `on_config_change(&self, config: C)` runs on `Runtime::reconfigure`, where
+///   `C` is any deserializable configuration type.
+///
+/// Hooks may return nothing or a `Result` whose error implements `Display`.
+/// Plugins constructed from a config take the same `config = MyConfig` argument
+/// as on `#[plugin_impl]`.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use plugy_macros::plugin_hooks;
+///
+/// #[plugin_hooks]
+/// impl MyGreetPlugin {
+///     fn on_load(&self) -> Result<(), String> {
+///         Ok(())
+///     }
+///
+///     fn on_config_change(&self, greeting: String) {}
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn plugin_hooks(metadata: TokenStream, input: TokenStream) -> TokenStream {
+    let config = match parse_config(metadata) {
+        Ok(config) => config,
+        Err(e) => return e.write_errors().into(),
+    };
+    let with_instance = instance_accessor(&config);
+    let cur_impl: proc_macro2::TokenStream = input.clone().into();
+    let imp = parse_macro_input!(input as ItemImpl);
+    let ty = &imp.self_ty;
+    let constructor = instance_constructor(&config, ty, "hooks");
+    let hooks = imp.items.iter().filter_map(|i| match i {
+        ImplItem::Fn(m) => Some(m),
+        _ => None,
+    });
+    let mut derived = proc_macro2::TokenStream::new();
+    for m in hooks {
+        let method_name = &m.sig.ident;
+        let arity = match method_name.to_string().as_str() {
+            "on_load" | "on_unload" => 0,
+            "on_config_change" => 1,
+            _ => {
+                return syn::Error::new_spanned(
+                    method_name,
+                    "unknown hook, expected `on_load`, `on_unload` or `on_config_change`",
+                )
+                .to_compile_error()
+                .into()
+            }
+        };
+        let args: Vec<_> = m
+            .sig
+            .inputs
+            .iter()
+            .filter_map(|arg| match arg {
+                syn::FnArg::Receiver(_) => None,
+                syn::FnArg::Typed(t) => Some(t),
+            })
+            .collect();
+        if args.len() != arity {
+            return syn::Error::new_spanned(
+                &m.sig,
+                format!("`{method_name}` takes {arity} argument(s) besides `&self`"),
+            )
+            .to_compile_error()
+            .into();
+        }
+        let GuestArgs {
+            bindings,
+            types,
+            values,
+        } = guest_args(&m.sig.inputs);
+        let result = if returns_result(&m.sig.output) {
+            quote! {
+                __plugy_self.#method_name(#(#values),*).map_err(|e| e.to_string())
+            }
+        } else {
+            quote! {{
+                __plugy_self.#method_name(#(#values),*);
+                Ok(())
+            }}
+        };
+        let expose_name_ident =
+            Ident::new(&format!("_plugy_hook_{method_name}"), Span::call_site());
+        derived.extend(quote! {
+            #[no_mangle]
+            pub unsafe extern "C" fn #expose_name_ident(value: u64) -> u64 {
+                let (#(#bindings),*): (#(#types),*) = plugy::core::guest::read_msg(value);
+                #with_instance(|__plugy_self: &mut #ty| {
+                    let result: Result<(), String> = #result;
+                    plugy::core::guest::write_msg(&result)
+                })
+            }
+        });
+    }
+
+    quote! {
+        #cur_impl
+        #constructor
+        #derived
+    }
+    .into()
+}
+
+/// The arguments of `#[plugin_import]`.
+#[derive(FromMeta)]
+struct ImportArgs {
+    file: ExprArg,
+    name: Option<String>,
+    data: Option<Ident>,
+    /// Includes the module in the host binary at compile time.
+    #[darling(default)]
+    embed: bool,
+    /// An environment variable overriding the path at runtime.
+    env: Option<String>,
+    /// Resolves relative paths against the crate's manifest directory rather
+    /// than the working directory.
+    #[darling(default)]
+    manifest_dir: bool,
+}
+
+/// An expression given as a macro argument and used as is, where darling would
+/// parse the contents of string literals instead.
+struct ExprArg(syn::Expr);
+
+impl FromMeta for ExprArg {
+    fn from_expr(expr: &syn::Expr) -> darling::Result<Self> {
+        Ok(ExprArg(expr.clone()))
+    }
+}
+
+/// A procedural macro loading a plugin from a wasm file on the host.
+///
+/// It implements `PluginLoader` for the annotated struct, reading the module
+/// from `file`. The plugin is named after the struct unless `name = "..."` is
+/// given.
+///
+/// # Arguments
+///
+/// - `file = "..."`: the path of the wasm module, relative to the working
+///   directory of the host unless `manifest_dir` is given.
+/// - `manifest_dir`: resolves a relative `file` against the directory of the
+///   crate's `Cargo.toml` instead.
+/// - `embed`: includes the module in the host binary at compile time, so it
+///   needs no file at runtime. `file` must then be a string literal, and is
+///   relative to the manifest directory.
+/// - `env = "VAR"`: reads the module from the path in the environment variable
+///   `VAR` instead when it is set, also for embedded modules.
+/// - `name = "..."`: the name of the plugin.
+/// - `data = field`: the field moved into the plugin data, see below.
+///
+/// With `name` or `data = field`, it also implements the conversion into
+/// `Plugin`, moving `field` into the plugin data so the runtime can load the
+/// struct with `Runtime::load_with`. Without `data`, the plugin data is the
+/// default `Vec<u8>`.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// #[plugin_import(file = "target/wasm32-unknown-unknown/debug/foo_plugin.wasm", data = addr)]
+/// struct FooPlugin {
+///     addr: Addr,
+/// }
+///
+/// let handle = runtime.load_with(FooPlugin { addr }).await?;
+/// ```
 #[proc_macro_attribute]
 pub fn plugin_import(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = match NestedMeta::parse_meta_list(args.into())
+        .map_err(darling::Error::from)
+        .and_then(|args| ImportArgs::from_list(&args))
+    {
+        Ok(args) => args,
+        Err(e) => return e.write_errors().into(),
+    };
     let input = parse_macro_input!(input as DeriveInput);
     let struct_name = &input.ident;
-    let parsed = syn::parse2::<MetaNameValue>(args.into()).unwrap();
-    assert_eq!(parsed.path.to_token_stream().to_string(), "file");
-    let file_path = parsed.value;
+    let file_path = &args.file.0;
+    let read = if args.embed {
+        let syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(file),
+            ..
+        }) = file_path
+        else {
+            return syn::Error::new_spanned(
+                file_path,
+                "`embed` needs `file` to be a string literal",
+            )
+            .to_compile_error()
+            .into();
+        };
+        let path = if std::path::Path::new(&file.value()).is_absolute() {
+            quote! { #file }
+        } else {
+            quote! { concat!(env!("CARGO_MANIFEST_DIR"), "/", #file) }
+        };
+        quote! { Ok(include_bytes!(#path).to_vec()) }
+    } else {
+        let path = if args.manifest_dir {
+            quote! { std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join(#file_path) }
+        } else {
+            quote! { std::path::PathBuf::from(#file_path) }
+        };
+        quote! {
+            let path = #path;
+            anyhow::Context::with_context(std::fs::read(&path), || {
+                format!("could not read plugin from {}", path.display())
+            })
+        }
+    };
+    let env_override = args.env.as_ref().map(|var| {
+        quote! {
+            if let Some(path) = std::env::var_os(#var) {
+                return anyhow::Context::with_context(std::fs::read(&path), || {
+                    format!("could not read plugin from {} set in `{}`", path.to_string_lossy(), #var)
+                });
+            }
+        }
+    });
+    let plugin_type = struct_name.to_string();
+    // The runtime keys plugins by the loader's name, which the plugin reports
+    // as its own too
+    let name = args.name.clone().unwrap_or_else(|| plugin_type.clone());
+
+    let conversion = match &args.data {
+        Some(field) => {
+            let ty = match &input.data {
+                syn::Data::Struct(data) => data
+                    .fields
+                    .iter()
+                    .find(|f| f.ident.as_ref() == Some(field))
+                    .map(|f| &f.ty),
+                _ => None,
+            };
+            let Some(ty) = ty else {
+                return syn::Error::new_spanned(
+                    field,
+                    format!("`{struct_name}` has no field `{field}`"),
+                )
+                .to_compile_error()
+                .into();
+            };
+            Some(quote! {
+                impl From<#struct_name> for plugy::runtime::Plugin<#ty> {
+                    fn from(plugin: #struct_name) -> Self {
+                        plugy::runtime::Plugin {
+                            name: #name.to_owned(),
+                            plugin_type: #plugin_type.to_owned(),
+                            data: plugin.#field,
+                        }
+                    }
+                }
+            })
+        }
+        None => args.name.is_some().then(|| {
+            quote! {
+                impl From<#struct_name> for plugy::runtime::Plugin {
+                    fn from(_: #struct_name) -> Self {
+                        plugy::runtime::Plugin {
+                            name: #name.to_owned(),
+                            plugin_type: #plugin_type.to_owned(),
+                            data: Default::default(),
+                        }
+                    }
+                }
+            }
+        }),
+    };
 
     quote! {
         #input
@@ -235,33 +1585,198 @@ pub fn plugin_import(args: TokenStream, input: TokenStream) -> TokenStream {
         impl PluginLoader for #struct_name {
             fn bytes(&self) -> std::pin::Pin<std::boxed::Box<dyn std::future::Future<Output = Result<Vec<u8>, anyhow::Error>>>> {
                 std::boxed::Box::pin(async {
-                    let res = std::fs::read(#file_path)?;
-                    Ok(res)
+                    #env_override
+                    #read
                 })
             }
             fn name(&self) -> &'static str {
-                std::any::type_name::<Self>()
+                #name
             }
         }
+
+        #conversion
     }.into()
 }
 
+/// Whether a context method returns a `Result`, in which case errors raised by
+/// the host glue are handed to the guest through the method's own error type.
+fn returns_result(output: &syn::ReturnType) -> bool {
+    match output {
+        syn::ReturnType::Type(_, ty) => match &**ty {
+            syn::Type::Path(path) => path
+                .path
+                .segments
+                .last()
+                .is_some_and(|seg| seg.ident == "Result"),
+            _ => false,
+        },
+        syn::ReturnType::Default => false,
+    }
+}
+
+/// The arguments of `#[context]`.
+#[derive(Default, FromMeta)]
+struct ContextArgs {
+    /// The data of the plugins the context is linked for, `Vec<u8>` if left out.
+    #[darling(default)]
+    data: Option<TypeArg>,
+    /// The wasm module the context functions are imported from.
+    #[darling(default)]
+    namespace: Option<String>,
+    /// The case the imported function names are converted to.
+    #[darling(default)]
+    rename_all: Option<RenameRule>,
+}
+
+/// A case convention for `rename_all`, spelled as in serde.
+struct RenameRule(Case<'static>);
+
+impl FromMeta for RenameRule {
+    fn from_string(value: &str) -> darling::Result<Self> {
+        let case = match value {
+            "lowercase" => Case::Flat,
+            "UPPERCASE" => Case::UpperFlat,
+            "PascalCase" => Case::Pascal,
+            "camelCase" => Case::Camel,
+            "snake_case" => Case::Snake,
+            "SCREAMING_SNAKE_CASE" => Case::UpperSnake,
+            "kebab-case" => Case::Kebab,
+            "SCREAMING-KEBAB-CASE" => Case::UpperKebab,
+            _ => return Err(darling::Error::unknown_value(value)),
+        };
+        Ok(RenameRule(case))
+    }
+}
+
+impl ContextArgs {
+    /// The wasm module context functions are imported from, named after the
+    /// context's module unless overridden.
+    fn namespace(&self, mod_name: &Ident) -> String {
+        match &self.namespace {
+            Some(namespace) => namespace.clone(),
+            None => format!("plugy:{mod_name}"),
+        }
+    }
+
+    /// The name `method` is imported under. Functions in the shared `env`
+    /// module are prefixed to stay clear of other imports.
+    fn import_name(&self, namespace: &str, method: &Ident) -> String {
+        let name = match &self.rename_all {
+            Some(RenameRule(case)) => method.to_string().to_case(*case),
+            None => method.to_string(),
+        };
+        if namespace == "env" {
+            format!("_plugy_context_{name}")
+        } else {
+            name
+        }
+    }
+}
+
+/// Checks that the functions of a context take the caller first.
+fn check_context_fns(input: &ItemImpl) -> syn::Result<()> {
+    for item in &input.items {
+        let syn::ImplItem::Fn(method) = item else {
+            continue;
+        };
+        let sig = &method.sig;
+        match sig.inputs.first() {
+            Some(FnArg::Typed(_)) => {}
+            Some(receiver @ FnArg::Receiver(_)) => {
+                return Err(syn::Error::new_spanned(
+                    receiver,
+                    "context functions take the caller as their first argument instead of `self`",
+                ))
+            }
+            None => {
+                return Err(syn::Error::new_spanned(
+                    sig,
+                    "context functions take the caller as their first argument, \
+                     as in `caller: &mut plugy::runtime::Caller<'_, Plugin<D>>`",
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A procedural macro attribute exposing host functions to plugins.
+///
+/// Each function of the impl takes the caller first and is linked by
+/// `Runtime::context`. Plugins get a module named after the type, whose
+/// `sync` struct has the same functions minus the caller, called over the
+/// wasm boundary.
+///
+/// Functions are either `async`, for host work that waits, or plain `fn`s,
+/// which are cheaper to call as no future is boxed per call. Clock reads,
+/// hashing and lookups are good candidates for the latter.
+///
+/// # Arguments
+///
+/// - `data = Type`: the data of the plugins the context is linked for,
+///   `Vec<u8>` if left out, such as `data = Option<Config>`.
+/// - `namespace = "..."`: the wasm module the functions are imported from,
+///   `plugy:{module}` by default, such as `plugy:logger` for `Logger`. In the
+///   shared `env` module, where contexts lived before namespaces, functions
+///   are imported as `_plugy_context_{name}`.
+/// - `rename_all = "..."`: converts the imported names to a case, spelled as
+///   in serde, such as `"camelCase"`. Useful to match host functions plugins
+///   were not built against with plugy.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use plugy::runtime::{Caller, Plugin};
+///
+/// pub struct Logger;
+///
+/// #[plugy::macros::context(data = my_app::Addr, namespace = "logger")]
+/// impl Logger {
+///     pub async fn log(_caller: &mut Caller<'_, Plugin<my_app::Addr>>, text: String) {
+///         println!("{text}");
+///     }
+///
+///     pub fn level(_caller: &mut Caller<'_, Plugin<my_app::Addr>>) -> u8 {
+///         3
+///     }
+/// }
+/// ```
 #[proc_macro_attribute]
 pub fn context(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = match parse_type_args(args.into(), &["data"])
+        .map_err(darling::Error::from)
+        .and_then(|args| ContextArgs::from_list(&args))
+    {
+        Ok(args) => args,
+        Err(e) => return e.write_errors().into(),
+    };
     // Parse the input as an ItemImpl
     let input = parse_macro_input!(input as ItemImpl);
 
-    let data_ident = &args
-        .into_iter()
-        .nth(2)
-        .map(|d| Ident::new(&d.to_string(), d.span().into()))
-        .unwrap_or(Ident::new("_", Span::call_site()));
+    let data_ident = match &args.data {
+        Some(TypeArg(ty)) => ty.clone(),
+        None => syn::parse_quote!(Vec<u8>),
+    };
+    if let Err(e) = check_context_fns(&input) {
+        return e.to_compile_error().into();
+    }
 
     // Get the name of the struct being implemented
     let struct_name = &input.self_ty.to_token_stream();
+    let Some(struct_ident) = (match &*input.self_ty {
+        syn::Type::Path(path) if path.qself.is_none() => path.path.get_ident(),
+        _ => None,
+    }) else {
+        return syn::Error::new_spanned(
+            &input.self_ty,
+            "`#[context]` applies to impls of a type named by a plain identifier",
+        )
+        .to_compile_error()
+        .into();
+    };
 
     let mod_name = Ident::new(
-        &struct_name.to_string().to_case(Case::Snake),
+        &struct_ident.to_string().to_case(Case::Snake),
         Span::call_site(),
     );
 
@@ -269,6 +1784,8 @@ pub fn context(args: TokenStream, input: TokenStream) -> TokenStream {
 
     let mut links = Vec::new();
 
+    let mut imports = Vec::new();
+
     // Iterate over the items in the impl block to find methods
     let generated_methods = input
         .items
@@ -277,87 +1794,110 @@ pub fn context(args: TokenStream, input: TokenStream) -> TokenStream {
             if let syn::ImplItem::Fn(method) = item {
                 let generics = &method.sig.generics;
                 let method_name = &method.sig.ident;
-                let method_args: Vec<_> = method
+                // Skip &caller, the receivers were ruled out by `check_context_fns`
+                let inputs: Vec<&syn::PatType> = method
                     .sig
                     .inputs
                     .iter()
-                    .skip(1) // Skip &caller
-                    .map(|arg| {
-                        if let FnArg::Typed(pat_type) = arg {
-                            pat_type.to_token_stream()
-                        } else {
-                            panic!("Unsupported function argument type");
-                        }
-                    })
-                    .collect();
-                let method_pats: Vec<_> = method
-                    .sig
-                    .inputs
-                    .iter()
-                    .skip(1) // Skip &caller
-                    .map(|arg| {
-                        if let FnArg::Typed(pat_type) = arg {
-                            pat_type.pat.to_token_stream()
-                        } else {
-                            panic!("Unsupported function argument type");
-                        }
+                    .skip(1)
+                    .filter_map(|arg| match arg {
+                        FnArg::Typed(pat_type) => Some(pat_type),
+                        FnArg::Receiver(_) => None,
                     })
                     .collect();
+                let method_args: Vec<_> = inputs.iter().map(|arg| arg.to_token_stream()).collect();
+                let method_pats: Vec<_> = inputs.iter().map(|arg| arg.pat.to_token_stream()).collect();
                 let return_type = &method.sig.output;
                 let extern_method_name = Ident::new(
                     &format!("_plugy_context_{}", method_name),
                     Span::call_site(),
                 );
+                let namespace = args.namespace(&mod_name);
+                let import_name = args.import_name(&namespace, method_name);
+                imports.push(quote!((#namespace, #import_name)));
 
                 externs.push(quote::quote! {
+                    #[link(wasm_import_module = #namespace)]
                     extern "C" {
+                        #[link_name = #import_name]
                         fn #extern_method_name(ptr: u64) -> u64;
                     }
                 });
 
-                let extern_method_name_str = extern_method_name.to_string();
-
-                links.push(quote! {
-                    linker
-                        .func_wrap_async(
-                            "env",
-                            #extern_method_name_str,
-                            move |mut caller: plugy::runtime::Caller<_>,
-                                ptr: (u64,)|
-                                -> Box<dyn std::future::Future<Output = u64> + Send> {
-                                use plugy::core::bitwise::{from_bitwise, into_bitwise};
-                                Box::new(async move {
-                                    let store = caller.data().clone().unwrap();
-                                    let plugy::runtime::RuntimeCaller {
-                                        memory,
-                                        alloc_fn,
-                                        dealloc_fn,
-                                        plugin
-                                    } = store;
-
-                                    let (ptr, len) = from_bitwise(ptr.0);
-                                    let mut buffer = vec![0u8; len as _];
-                                    memory.read(&mut caller, ptr as _, &mut buffer).unwrap();
-                                    dealloc_fn
-                                        .call_async(&mut caller, into_bitwise(ptr, len))
-                                        .await
-                                        .unwrap();
-                                    let (#(#method_pats),*) = bincode::deserialize(&buffer).unwrap();
-                                    let buffer =
-                                        bincode::serialize(&#struct_name::#method_name(&mut caller, #(#method_pats),*).await)
-                                            .unwrap();
-                                    let ptr = alloc_fn
-                                        .call_async(&mut caller, buffer.len() as _)
-                                        .await
-                                        .unwrap();
-                                    memory.write(&mut caller, ptr as _, &buffer).unwrap();
-                                    into_bitwise(ptr, buffer.len() as _)
-                                })
-                            },
-                        )
-                        .unwrap();
+                let method_name_str = method_name.to_string();
+
+                links.push(if method.sig.asyncness.is_none() {
+                    quote! {
+                        linker
+                            .func_wrap(
+                                #namespace,
+                                #import_name,
+                                move |mut caller: plugy::runtime::Caller<_>, ptr: u64| -> anyhow::Result<u64> {
+                                    let buffer = plugy::runtime::read_sync_context_args(&mut caller, ptr);
+                                    let result = match &buffer {
+                                        Ok(buffer) => match plugy::runtime::deserialize_context_args(buffer) {
+                                            Ok((#(#method_pats),*)) => Ok(#struct_name::#method_name(&mut caller, #(#method_pats),*)),
+                                            Err(e) => Err(e),
+                                        },
+                                        Err(e) => Err(e.clone()),
+                                    };
+                                    plugy::runtime::stash_context_result(&mut caller, result)
+                                },
+                            )?;
+                    }
+                } else {
+                    quote! {
+                        linker
+                            .func_wrap_async(
+                                #namespace,
+                                #import_name,
+                                move |mut caller: plugy::runtime::Caller<_>,
+                                    ptr: (u64,)|
+                                    -> Box<dyn std::future::Future<Output = anyhow::Result<u64>> + Send> {
+                                    Box::new(async move {
+                                        let buffer = plugy::runtime::read_context_args(&mut caller, ptr.0).await;
+                                        let result = match &buffer {
+                                            Ok(buffer) => match plugy::runtime::deserialize_context_args(buffer) {
+                                                Ok((#(#method_pats),*)) => Ok(#struct_name::#method_name(&mut caller, #(#method_pats),*).await),
+                                                Err(e) => Err(e),
+                                            },
+                                            Err(e) => Err(e.clone()),
+                                        };
+                                        plugy::runtime::write_context_result(&mut caller, result).await
+                                    })
+                                },
+                            )?;
+                    }
                 });
 
+                // Synchronous functions leave the guest buffers to the guest
+                let read_envelope = if method.sig.asyncness.is_none() {
+                    quote! {
+                        unsafe {
+                            let len = #extern_method_name(ptr);
+                            plugy::core::guest::dealloc(ptr);
+                            plugy::core::guest::take_context_result(len)
+                        }
+                    }
+                } else {
+                    quote! {
+                        unsafe { plugy::core::guest::read_msg(#extern_method_name(ptr)) }
+                    }
+                };
+
+                let unwrap_envelope = if returns_result(return_type) {
+                    quote! {
+                        match envelope {
+                            Ok(res) => res,
+                            Err(e) => Err(e.into()),
+                        }
+                    }
+                } else {
+                    quote! {
+                        envelope.unwrap_or_else(|e| panic!("context call `{}` failed: {e}", #method_name_str))
+                    }
+                };
+
                 Some(quote! {
                     #[allow(unused_variables)]
                     pub fn #method_name #generics (#(#method_args),*) #return_type {
@@ -365,7 +1905,8 @@ pub fn context(args: TokenStream, input: TokenStream) -> TokenStream {
                         {
                             let args = (#(#method_pats),*);
                             let ptr = plugy::core::guest::write_msg(&args);
-                            unsafe { plugy::core::guest::read_msg(#extern_method_name(ptr)) }
+                            let envelope: Result<_, plugy::core::error::ContextError> = #read_envelope;
+                            #unwrap_envelope
                         }
                         #[cfg(not(target_arch = "wasm32"))]
                         panic!("You are trying to call wasm methods outside of wasm32")
@@ -382,8 +1923,13 @@ pub fn context(args: TokenStream, input: TokenStream) -> TokenStream {
         #input
         #[cfg(not(target_arch = "wasm32"))]
         impl plugy::runtime::Context<#data_ident> for #struct_name {
-            fn link(&self, linker: &mut plugy::runtime::Linker<plugy::runtime::Plugin<#data_ident>>) {
+            fn imports(&self) -> Vec<(&'static str, &'static str)> {
+                vec![#(#imports),*]
+            }
+
+            fn link(&self, linker: &mut plugy::runtime::Linker<plugy::runtime::Plugin<#data_ident>>) -> anyhow::Result<()> {
                 #(#links)*
+                Ok(())
             }
         }
 
@@ -408,3 +1954,32 @@ pub fn context(args: TokenStream, input: TokenStream) -> TokenStream {
     // Return the generated code as a TokenStream
     generated.into()
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn hashes_signatures_by_last_path_segment() {
+        let short: syn::Signature = syn::parse_quote! {
+            fn greet(&self, name: String, tags: Vec<String>) -> Option<String>
+        };
+        let long: syn::Signature = syn::parse_quote! {
+            fn greet(&self, name: std::string::String, tags: ::std::vec::Vec<std::string::String>) -> core::option::Option<String>
+        };
+        assert_eq!(signature_hash(&short), signature_hash(&long));
+
+        let other: syn::Signature = syn::parse_quote! {
+            fn greet(&self, name: String, tags: Vec<u8>) -> Option<String>
+        };
+        assert_ne!(signature_hash(&short), signature_hash(&other));
+    }
+
+    #[test]
+    fn hashes_associated_types_by_name() {
+        let assoc: syn::Signature = syn::parse_quote! { fn make(&self) -> Self::Output };
+        let named: syn::Signature = syn::parse_quote! { fn make(&self) -> Output };
+        assert_ne!(signature_hash(&assoc), signature_hash(&named));
+        assert_eq!(signature_type(quote! { Self::Output }), "Self::Output");
+    }
+}
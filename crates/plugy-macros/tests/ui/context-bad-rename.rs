@@ -0,0 +1,12 @@
+use plugy::macros::context;
+
+struct Logger;
+
+#[context(rename_all = "Title Case")]
+impl Logger {
+    pub async fn log(_caller: &mut (), text: String) {
+        println!("{text}");
+    }
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use plugy::macros::context;
+
+struct Clock;
+
+#[context]
+impl Clock {
+    pub fn now() -> u64 {
+        42
+    }
+}
+
+fn main() {}
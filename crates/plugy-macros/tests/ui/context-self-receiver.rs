@@ -0,0 +1,12 @@
+use plugy::macros::context;
+
+struct Logger;
+
+#[context]
+impl Logger {
+    pub async fn log(&self, text: String) {
+        println!("{text}");
+    }
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use plugy::macros::context;
+
+struct Logger;
+
+#[context(state = Vec)]
+impl Logger {
+    pub async fn log(_caller: &mut (), text: String) {
+        println!("{text}");
+    }
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+use plugy::macros::context;
+use plugy::runtime::{Caller, Plugin};
+
+pub struct Addr;
+
+pub struct Logger;
+
+#[context(data = Option<Addr>, namespace = "logger")]
+impl Logger {
+    pub async fn log(_caller: &mut Caller<'_, Plugin<Option<Addr>>>, text: String) {
+        println!("{text}");
+    }
+}
+
+fn main() {}
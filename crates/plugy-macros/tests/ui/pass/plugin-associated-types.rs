@@ -0,0 +1,19 @@
+use plugy::macros::plugin;
+
+#[plugin(types(Output = String, Items = "Vec<String>"))]
+pub trait Maker {
+    type Output;
+    type Items;
+    type Raw;
+    fn make(&self) -> Self::Output;
+    fn items(&self) -> Option<Self::Items>;
+    fn raw(&self) -> Self::Raw;
+}
+
+pub async fn make(maker: &MakerWrapper<(), ()>) -> anyhow::Result<(String, Option<Vec<String>>)> {
+    // Associated types left out are exchanged as raw bytes
+    let _: Vec<u8> = maker.raw().await?;
+    Ok((maker.make().await?, maker.items().await?))
+}
+
+fn main() {}
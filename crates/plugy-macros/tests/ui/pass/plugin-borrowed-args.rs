@@ -0,0 +1,12 @@
+use plugy::macros::plugin;
+
+#[plugin]
+pub trait Hasher {
+    fn hash(&self, text: &str, bytes: &[u8]) -> u64;
+}
+
+pub async fn hash(hasher: &HasherWrapper<(), ()>) -> anyhow::Result<u64> {
+    hasher.hash("text", &[1, 2, 3]).await
+}
+
+fn main() {}
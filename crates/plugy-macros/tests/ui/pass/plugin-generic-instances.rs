@@ -0,0 +1,18 @@
+use plugy::macros::plugin;
+
+#[plugin(instances(Codec<u32>, Codec<String>))]
+pub trait Codec<T> {
+    fn encode(&self, value: T) -> Vec<u8>;
+    fn decode(&self, bytes: Vec<u8>) -> Option<T>;
+}
+
+pub async fn round_trip(
+    numbers: &CodecWrapper<u32, (), ()>,
+    strings: &CodecWrapper<String, (), ()>,
+) -> anyhow::Result<(Option<u32>, Option<String>)> {
+    let number = numbers.decode(numbers.encode(1).await?).await?;
+    let string = strings.decode(strings.encode("one".into()).await?).await?;
+    Ok((number, string))
+}
+
+fn main() {}
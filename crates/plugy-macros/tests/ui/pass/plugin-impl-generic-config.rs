@@ -0,0 +1,52 @@
+use plugy::macros::{plugin, plugin_hooks, plugin_impl};
+
+#[plugin]
+pub trait Sized {
+    fn len(&self) -> u32;
+}
+
+#[derive(Default)]
+pub struct Bytes(Vec<u8>);
+
+impl From<Vec<u8>> for Bytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        Bytes(bytes)
+    }
+}
+
+#[plugin_impl(config = Vec<u8>)]
+impl Sized for Bytes {
+    fn len(&self) -> u32 {
+        self.0.len() as u32
+    }
+}
+
+#[plugin_hooks(config = Vec<u8>)]
+impl Bytes {
+    fn on_load(&self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+#[plugin]
+pub trait Greeter {
+    fn greet(&self) -> String;
+}
+
+#[derive(Default)]
+pub struct Named(Option<String>);
+
+impl From<Option<String>> for Named {
+    fn from(name: Option<String>) -> Self {
+        Named(name)
+    }
+}
+
+#[plugin_impl(config = std::option::Option<String>)]
+impl Greeter for Named {
+    fn greet(&self) -> String {
+        format!("Hello, {}", self.0.as_deref().unwrap_or("world"))
+    }
+}
+
+fn main() {}
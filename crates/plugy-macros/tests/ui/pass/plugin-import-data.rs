@@ -0,0 +1,18 @@
+use plugy::core::PluginLoader;
+use plugy::macros::plugin_import;
+use plugy::runtime::Plugin;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Addr(u32);
+
+#[plugin_import(file = "foo_plugin.wasm", data = addr)]
+struct FooPlugin {
+    addr: Addr,
+}
+
+fn main() {
+    let plugin = Plugin::<Addr>::from(FooPlugin { addr: Addr(7) });
+    assert_eq!(plugin.data, Addr(7));
+    assert_eq!(plugin.name, "FooPlugin");
+    assert_eq!(FooPlugin { addr: Addr(7) }.name(), "FooPlugin");
+}
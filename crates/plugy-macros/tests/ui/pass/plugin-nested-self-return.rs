@@ -0,0 +1,14 @@
+use plugy::macros::plugin;
+
+#[plugin]
+pub trait Pair {
+    fn pair(&self) -> (Self, u8)
+    where
+        Self: Sized;
+    fn pairs(&self) -> [Self; 2]
+    where
+        Self: Sized;
+    fn id(&self) -> u8;
+}
+
+fn main() {}
@@ -0,0 +1,16 @@
+use plugy::macros::plugin;
+
+#[plugin]
+pub trait Greeter {
+    fn version() -> String;
+    fn greet(&self, name: String) -> String;
+}
+
+// The trait stays usable as `dyn Greeter`
+pub struct Greeters(pub Vec<Box<dyn Greeter>>);
+
+pub async fn version(greeter: &GreeterWrapper<(), ()>) -> anyhow::Result<String> {
+    greeter.version().await
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use plugy::macros::plugin;
+use std::fmt::Debug;
+
+#[plugin]
+pub trait Greeter: std::fmt::Display + Debug + AsRef<str> + Send + Sync {
+    fn greet(&self) -> String;
+}
+
+fn main() {}
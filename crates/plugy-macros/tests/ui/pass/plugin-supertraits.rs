@@ -0,0 +1,19 @@
+use plugy::macros::plugin;
+
+#[plugin]
+pub trait Named {
+    fn name(&self) -> String;
+}
+
+#[plugin]
+pub trait Greeter: Named + Send {
+    fn greet(&self) -> String;
+}
+
+pub async fn introduce(greeter: &GreeterWrapper<(), ()>) -> anyhow::Result<String> {
+    let name = greeter.name().await?;
+    let greeting = greeter.greet().await?;
+    Ok(format!("{greeting}, {name}"))
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use plugy::macros::plugin;
+
+#[plugin]
+trait Greeter {
+    fn greet<T>(&self, name: T) -> String;
+}
+
+fn main() {}
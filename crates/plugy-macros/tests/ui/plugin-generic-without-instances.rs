@@ -0,0 +1,8 @@
+use plugy::macros::plugin;
+
+#[plugin]
+trait Codec<T> {
+    fn encode(&self, value: T) -> Vec<u8>;
+}
+
+fn main() {}
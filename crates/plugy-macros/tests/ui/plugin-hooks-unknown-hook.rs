@@ -0,0 +1,10 @@
+use plugy::macros::plugin_hooks;
+
+struct Greeter;
+
+#[plugin_hooks]
+impl Greeter {
+    fn on_start(&self) {}
+}
+
+fn main() {}
@@ -0,0 +1,16 @@
+use plugy::macros::plugin_impl;
+
+trait Greeter {
+    fn greet(self) -> String;
+}
+
+struct Foo;
+
+#[plugin_impl]
+impl Greeter for Foo {
+    fn greet(self) -> String {
+        "Hello".to_owned()
+    }
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use plugy::macros::plugin_impl;
+
+struct Foo;
+
+#[plugin_impl]
+impl Foo {
+    fn greet(&self) -> String {
+        "Hello".to_owned()
+    }
+}
+
+fn main() {}
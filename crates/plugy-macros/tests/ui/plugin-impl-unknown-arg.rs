@@ -0,0 +1,17 @@
+use plugy::macros::plugin_impl;
+
+trait Greeter {
+    fn greet(&self) -> String;
+}
+
+#[derive(Default)]
+struct Foo;
+
+#[plugin_impl(settings = Settings)]
+impl Greeter for Foo {
+    fn greet(&self) -> String {
+        "Hello".to_owned()
+    }
+}
+
+fn main() {}
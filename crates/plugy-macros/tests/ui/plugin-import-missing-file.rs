@@ -0,0 +1,6 @@
+use plugy::macros::plugin_import;
+
+#[plugin_import(name = "foo")]
+struct Foo;
+
+fn main() {}
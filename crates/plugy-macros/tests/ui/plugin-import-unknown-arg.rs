@@ -0,0 +1,6 @@
+use plugy::macros::plugin_import;
+
+#[plugin_import(file = "foo.wasm", path = "foo.wasm")]
+struct Foo;
+
+fn main() {}
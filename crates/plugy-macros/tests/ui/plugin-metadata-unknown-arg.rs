@@ -0,0 +1,6 @@
+use plugy::macros::plugin_metadata;
+
+#[plugin_metadata(name = "greeter", license = "MIT")]
+struct Greeter;
+
+fn main() {}
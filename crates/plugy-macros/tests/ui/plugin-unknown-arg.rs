@@ -0,0 +1,8 @@
+use plugy::macros::plugin;
+
+#[plugin(instance = u32)]
+trait Greeter {
+    fn greet(&self) -> String;
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use plugy::macros::plugin;
+
+pub trait Named {
+    fn name(&self) -> String;
+}
+
+#[plugin]
+pub trait Greeter: Named {
+    fn greet(&self) -> String;
+}
+
+fn main() {}
@@ -9,11 +9,29 @@ use async_lock::RwLock;
 use bincode::Error;
 use dashmap::DashMap;
 use plugy_core::bitwise::{from_bitwise, into_bitwise};
+pub use plugy_core::error::ContextError;
 use plugy_core::PluginLoader;
 use serde::{de::DeserializeOwned, Serialize};
+use std::collections::HashMap;
 use std::fmt;
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use std::{marker::PhantomData, sync::Arc};
-use wasmtime::{Engine, Instance, Module, Store};
+use supervisor::{Refusal, Supervisor, Verdict};
+use wasmtime::{Engine, Instance, InstancePre, Module, Store};
+
+mod metadata;
+mod supervisor;
+mod timer;
+
+pub use metadata::PluginMetadata;
+pub use supervisor::{Backoff, SupervisorPolicy};
+
+/// Lets the code generated by `#[plugin]` name serde through plugy.
+#[doc(hidden)]
+pub use serde;
 
 pub type CallerStore<D = Plugin> = Arc<RwLock<Store<Option<RuntimeCaller<D>>>>>;
 
@@ -21,6 +39,74 @@ pub type Caller<'a, D = Plugin> = wasmtime::Caller<'a, Option<RuntimeCaller<D>>>
 
 pub type Linker<D = Plugin> = wasmtime::Linker<Option<RuntimeCaller<D>>>;
 
+/// The prefix of guest exports backing plugin functions, followed by
+/// `{Trait}::{method}`.
+const GUEST_PREFIX: &str = "_plugy_guest_";
+
+/// The prefix of guest exports dispatching calls to any method of a trait,
+/// followed by the trait name.
+const DISPATCH_PREFIX: &str = "_plugy_dispatch_";
+
+/// The prefix of guest exports listing the methods of a trait, followed by the
+/// trait name.
+const METHODS_PREFIX: &str = "_plugy_methods_";
+
+/// The prefix of guest exports listing the signature hashes of a trait's
+/// methods, followed by the trait name.
+const SIGNATURES_PREFIX: &str = "_plugy_signatures_";
+
+/// The guest export constructing plugin instances, see `plugy_core::guest::_plugy_init`.
+const INIT: &str = "_plugy_init";
+
+/// The prefix of guest exports constructing the instance of a plugin type
+/// after [`INIT`], see `plugy_core::guest::construct_instance`.
+const CONSTRUCT_PREFIX: &str = "_plugy_construct_";
+
+/// The guest export telling the ABI version it was built against, see
+/// `plugy_core::guest::_plugy_abi_version`.
+const ABI_VERSION: &str = "_plugy_abi_version";
+
+/// The host import guests take the result of a synchronous context call
+/// through, see `plugy_core::guest::take_context_result`.
+const CONTEXT_TAKE: &str = "_plugy_context_take";
+
+/// The versions of the guest ABI this runtime can load plugins built against.
+///
+/// Guests built before the version was exported count as version 0, whose
+/// context calls answered without a result envelope, and are refused.
+pub const SUPPORTED_ABI_VERSIONS: RangeInclusive<u32> = 1..=plugy_core::ABI_VERSION;
+
+/// Lifecycle hooks a guest may export through `#[plugin_hooks]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Hook {
+    /// Runs once the plugin is instantiated, an error fails the load.
+    Load,
+    /// Runs right before the plugin is unloaded.
+    Unload,
+    /// Runs when the host hands the plugin a new configuration.
+    ConfigChange,
+}
+
+impl Hook {
+    fn name(self) -> &'static str {
+        match self {
+            Hook::Load => "on_load",
+            Hook::Unload => "on_unload",
+            Hook::ConfigChange => "on_config_change",
+        }
+    }
+
+    fn export(self) -> &'static str {
+        match self {
+            Hook::Load => "_plugy_hook_on_load",
+            Hook::Unload => "_plugy_hook_on_unload",
+            Hook::ConfigChange => "_plugy_hook_on_config_change",
+        }
+    }
+}
+
+type PluginStore<P> = Store<Option<RuntimeCaller<P>>>;
+
 /// A runtime environment for managing plugins and instances.
 ///
 /// The `Runtime` struct provides a runtime environment for managing plugins
@@ -49,10 +135,147 @@ where
 {
     engine: Engine,
     linker: Linker<P>,
-    modules: DashMap<&'static str, RuntimeModule<P>>,
+    /// The context that linked each `(module, name)` import.
+    imports: HashMap<(&'static str, &'static str), &'static str>,
+    modules: DashMap<&'static str, Arc<RuntimeModule<P>>>,
+    recovery: RecoveryPolicy,
+    events: Events,
+    ticking: AtomicBool,
+    closed: AtomicBool,
     structure: PhantomData<T>,
 }
 
+/// What the runtime does with a plugin whose instance trapped.
+///
+/// After a trap the memory and globals of the instance may be left in an
+/// inconsistent state, so the instance is marked as poisoned and never called
+/// again as is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RecoveryPolicy {
+    /// Re-instantiate the plugin from its cached module, keeping its `Plugin<D>` data.
+    #[default]
+    Restart,
+    /// Leave the plugin poisoned; every call fails until it is loaded again.
+    Poison,
+    /// Restart with backoff, and quarantine the plugin when it keeps failing.
+    Supervised(SupervisorPolicy),
+}
+
+/// Notable things happening to loaded plugins, see [`Runtime::on_event`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RuntimeEvent {
+    /// A call into the plugin trapped and its instance was marked as poisoned.
+    Poisoned { plugin: &'static str, error: String },
+    /// The plugin was re-instantiated after being poisoned.
+    Restarted { plugin: &'static str },
+    /// Re-instantiating a poisoned plugin failed, it stays poisoned.
+    RestartFailed { plugin: &'static str, error: String },
+    /// The plugin failed too often and was quarantined by its supervisor.
+    Quarantined {
+        plugin: &'static str,
+        failures: usize,
+    },
+    /// A quarantined plugin was re-enabled through [`Runtime::reenable`].
+    Reenabled { plugin: &'static str },
+    /// A guest hook returned an error or trapped.
+    HookFailed {
+        plugin: &'static str,
+        hook: &'static str,
+        error: String,
+    },
+    /// The plugin was unloaded and its store dropped.
+    Unloaded { plugin: &'static str },
+}
+
+type Listener = Arc<dyn Fn(&RuntimeEvent) + Send + Sync>;
+
+/// The listeners registered through [`Runtime::on_event`], shared with every module.
+#[derive(Clone, Default)]
+struct Events(Arc<std::sync::RwLock<Vec<Listener>>>);
+
+impl Events {
+    fn emit(&self, event: RuntimeEvent) {
+        let listeners = self.0.read().unwrap_or_else(|e| e.into_inner());
+        for listener in listeners.iter() {
+            listener(&event);
+        }
+    }
+}
+
+/// Errors returned by the runtime when a plugin cannot be called.
+///
+/// They are wrapped in an `anyhow::Error` and can be recovered with `downcast_ref`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PluginError {
+    /// The plugin trapped earlier and has not been restarted.
+    Poisoned(&'static str),
+    /// The plugin failed too often and is quarantined until re-enabled.
+    Quarantined(&'static str),
+    /// The plugin failed recently and will be restarted once `retry_in` elapsed.
+    BackingOff {
+        plugin: &'static str,
+        retry_in: Duration,
+    },
+    /// The call ran past the supervisor's `call_timeout` and was interrupted.
+    TimedOut(&'static str),
+    /// The plugin was unloaded, or is being unloaded, and accepts no more calls.
+    Unloaded(&'static str),
+    /// The guest could not construct its plugin instance from the init bytes.
+    Construct { plugin: &'static str, error: String },
+    /// A lifecycle hook of the guest returned an error.
+    Hook {
+        plugin: &'static str,
+        hook: &'static str,
+        error: String,
+    },
+    /// The plugin was built against another signature of a trait method.
+    SignatureMismatch {
+        plugin: &'static str,
+        method: String,
+    },
+    /// The plugin was built against a version of the guest ABI outside of
+    /// [`SUPPORTED_ABI_VERSIONS`].
+    IncompatibleAbi { plugin: &'static str, version: u32 },
+}
+
+impl fmt::Display for PluginError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PluginError::Poisoned(name) => write!(f, "plugin `{name}` is poisoned after a trap"),
+            PluginError::Quarantined(name) => write!(f, "plugin `{name}` is quarantined"),
+            PluginError::BackingOff { plugin, retry_in } => {
+                write!(f, "plugin `{plugin}` is restarting, retry in {retry_in:?}")
+            }
+            PluginError::TimedOut(name) => write!(f, "plugin `{name}` timed out"),
+            PluginError::Unloaded(name) => write!(f, "plugin `{name}` has been unloaded"),
+            PluginError::Construct { plugin, error } => {
+                write!(f, "plugin `{plugin}` could not be constructed: {error}")
+            }
+            PluginError::Hook {
+                plugin,
+                hook,
+                error,
+            } => write!(f, "`{hook}` hook of plugin `{plugin}` failed: {error}"),
+            PluginError::SignatureMismatch { plugin, method } => write!(
+                f,
+                "plugin `{plugin}` was built against another signature of `{method}`, \
+                 rebuild it against the host's trait"
+            ),
+            PluginError::IncompatibleAbi { plugin, version } => write!(
+                f,
+                "plugin `{plugin}` was built against version {version} of the plugy ABI, \
+                 this runtime supports versions {} to {}",
+                SUPPORTED_ABI_VERSIONS.start(),
+                SUPPORTED_ABI_VERSIONS.end()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PluginError {}
+
 pub trait IntoCallable<P, D> {
     type Output;
     fn into_callable(handle: PluginHandle<Plugin<D>>) -> Self::Output;
@@ -85,24 +308,510 @@ impl Plugin {
 }
 
 /// Single runnable module
-#[allow(dead_code)]
+///
+/// Every handle of a plugin shares its module, so re-instantiating the module
+/// after a trap is visible to all of them.
 pub struct RuntimeModule<P>
 where
     P: 'static,
 {
-    inner: Module,
-    store: CallerStore<P>,
-    instance: Instance,
+    name: &'static str,
+    /// The type name of the loader the plugin came from, which
+    /// [`Runtime::get_plugin`] looks plugins up by.
+    loader: &'static str,
+    instance_pre: InstancePre<Option<RuntimeCaller<P>>>,
+    store: RwLock<Option<PluginStore<P>>>,
+    /// The config the guest constructs its plugin instance from: the one the
+    /// plugin was loaded or last reconfigured with. Plugins loaded without a
+    /// config have none and are constructed with `Default` on every restart.
+    init: Mutex<Option<Vec<u8>>>,
+    /// Every trait method the guest can dispatch, as `Trait::method`, along
+    /// with the dispatcher export reaching it.
+    methods: Vec<(String, String)>,
+    /// The signature hash of every trait method the guest was built against,
+    /// keyed by `Trait::method`.
+    signatures: HashMap<String, u64>,
+    poisoned: AtomicBool,
+    closing: AtomicBool,
+    /// Set while a shutdown runs the `on_unload` hook, which then yields to
+    /// the shutdown every epoch tick so that its deadline holds.
+    draining: AtomicBool,
+    /// Set once a shutdown gave up waiting for the in-flight call, which drops
+    /// the store when it is done.
+    abandoned: AtomicBool,
+    recovery: RecoveryPolicy,
+    supervisor: Option<Supervisor>,
+    events: Events,
+}
+
+impl<P> fmt::Debug for RuntimeModule<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RuntimeModule")
+            .field("name", &self.name)
+            .field("poisoned", &self.poisoned.load(Ordering::Acquire))
+            .field("recovery", &self.recovery)
+            .finish()
+    }
+}
+
+impl<P: Send> RuntimeModule<P> {
+    /// Instantiates `instance_pre` into a fresh store.
+    ///
+    /// The store data is left empty; it is filled in by the caller once the
+    /// plugin data is at hand, so that a failed instantiation loses nothing.
+    async fn instantiate(
+        instance_pre: &InstancePre<Option<RuntimeCaller<P>>>,
+    ) -> anyhow::Result<(PluginStore<P>, RuntimeCaller<()>)> {
+        let mut store = Store::new(instance_pre.module().engine(), None);
+        store.set_epoch_deadline(supervisor::NO_DEADLINE);
+        let instance = instance_pre.instantiate_async(&mut store).await?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .context("missing memory")?;
+        let alloc_fn = instance.get_typed_func(&mut store, "alloc")?;
+        let dealloc_fn = instance.get_typed_func(&mut store, "dealloc")?;
+        Ok((
+            store,
+            RuntimeCaller {
+                memory,
+                alloc_fn,
+                dealloc_fn,
+                instance,
+                plugin: (),
+                context_result: None,
+            },
+        ))
+    }
+
+    /// Marks the instance as poisoned after `error` interrupted the guest and
+    /// applies the recovery policy. Returns `error` for convenience.
+    async fn poison(&self, store: &mut PluginStore<P>, error: anyhow::Error) -> anyhow::Error {
+        let error = match error.downcast_ref::<wasmtime::Trap>() {
+            Some(wasmtime::Trap::Interrupt) => error.context(PluginError::TimedOut(self.name)),
+            _ => error,
+        };
+        self.poisoned.store(true, Ordering::Release);
+        self.events.emit(RuntimeEvent::Poisoned {
+            plugin: self.name,
+            error: format!("{error:#}"),
+        });
+        // A failed restart is reported and retried on the next call
+        match (self.recovery, &self.supervisor) {
+            (RecoveryPolicy::Restart, _) => {
+                let _ = self.restart(store).await;
+            }
+            (RecoveryPolicy::Supervised(_), Some(supervisor)) => {
+                match supervisor.record_failure() {
+                    Verdict::Restart(delay) if delay.is_zero() => {
+                        let _ = self.restart(store).await;
+                    }
+                    // Restarted by the first call after the backoff
+                    Verdict::Restart(_) => {}
+                    Verdict::Quarantine(failures) => {
+                        self.events.emit(RuntimeEvent::Quarantined {
+                            plugin: self.name,
+                            failures,
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+        error
+    }
+
+    /// Replaces a poisoned instance with a fresh one, carrying the plugin data over.
+    async fn restart(&self, store: &mut PluginStore<P>) -> anyhow::Result<()> {
+        match Self::instantiate(&self.instance_pre).await {
+            Ok((mut fresh, caller)) => {
+                let plugin = store
+                    .data_mut()
+                    .take()
+                    .context("missing plugin data")?
+                    .plugin;
+                *fresh.data_mut() = Some(caller.with_plugin(plugin));
+                *store = fresh;
+                // The fresh store carries the plugin data, a failed init or
+                // `on_load` is retried on the next restart from there
+                if let Err(error) = self.initialize(store).await {
+                    return Err(self.restart_failed(error));
+                }
+                // Boxed, as a failing `on_config_change` restarts in turn
+                if let Err(error) = Box::pin(self.hook(store, Hook::Load, &[])).await {
+                    return Err(self.restart_failed(error));
+                }
+                self.poisoned.store(false, Ordering::Release);
+                self.events
+                    .emit(RuntimeEvent::Restarted { plugin: self.name });
+                Ok(())
+            }
+            Err(error) => Err(self.restart_failed(error)),
+        }
+    }
+
+    fn restart_failed(&self, error: anyhow::Error) -> anyhow::Error {
+        self.events.emit(RuntimeEvent::RestartFailed {
+            plugin: self.name,
+            error: format!("{error:#}"),
+        });
+        error
+    }
+
+    /// Refuses guests built against an ABI version the runtime cannot call.
+    ///
+    /// Guests exporting no version predate it and count as version 0.
+    async fn check_abi(&self, store: &mut PluginStore<P>) -> anyhow::Result<()> {
+        let version = if self.exports(store, ABI_VERSION) {
+            let instance = store
+                .data()
+                .as_ref()
+                .context("missing plugin data")?
+                .instance;
+            instance
+                .get_typed_func::<(), u32>(&mut *store, ABI_VERSION)?
+                .call_async(&mut *store, ())
+                .await?
+        } else {
+            0
+        };
+        if !SUPPORTED_ABI_VERSIONS.contains(&version) {
+            return Err(PluginError::IncompatibleAbi {
+                plugin: self.name,
+                version,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Hands the init bytes to a freshly instantiated guest, which then
+    /// constructs its plugin instances from them.
+    async fn initialize(&self, store: &mut PluginStore<P>) -> anyhow::Result<()> {
+        if self.exports(store, INIT) {
+            let init = self.init.lock().unwrap_or_else(|e| e.into_inner()).clone();
+            self.invoke(store, INIT, init.as_deref().unwrap_or_default())
+                .await??;
+        }
+        for export in self.exports_prefixed(store, CONSTRUCT_PREFIX)? {
+            let output = self.invoke(store, &export, &[]).await??;
+            let result: Result<(), String> = bincode::deserialize(&output)?;
+            result.map_err(|error| PluginError::Construct {
+                plugin: self.name,
+                error,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Makes sure the instance can be called, restarting it if allowed.
+    async fn ensure_healthy(&self, store: &mut PluginStore<P>) -> anyhow::Result<()> {
+        if let Some(supervisor) = &self.supervisor {
+            supervisor.admit().map_err(|refusal| match refusal {
+                Refusal::Quarantined => PluginError::Quarantined(self.name),
+                Refusal::BackingOff(retry_in) => PluginError::BackingOff {
+                    plugin: self.name,
+                    retry_in,
+                },
+            })?;
+        }
+        if !self.poisoned.load(Ordering::Acquire) {
+            return Ok(());
+        }
+        match self.recovery {
+            RecoveryPolicy::Poison => Err(PluginError::Poisoned(self.name).into()),
+            _ => self.restart(store).await,
+        }
+    }
+
+    /// The store of a plugin that still accepts calls.
+    fn live<'a>(
+        &self,
+        store: &'a mut Option<PluginStore<P>>,
+    ) -> anyhow::Result<&'a mut PluginStore<P>> {
+        match store {
+            Some(store) if !self.closing.load(Ordering::Acquire) => Ok(store),
+            _ => Err(PluginError::Unloaded(self.name).into()),
+        }
+    }
+
+    /// Hands a serialized input to the `export` function of the guest and reads
+    /// back its serialized output.
+    ///
+    /// Failures happening while the guest runs poison the instance.
+    async fn call(
+        &self,
+        store: &mut PluginStore<P>,
+        export: &str,
+        buffer: &[u8],
+    ) -> anyhow::Result<Vec<u8>> {
+        match self.invoke(store, export, buffer).await? {
+            Ok(buffer) => Ok(buffer),
+            Err(error) => Err(self.poison(store, error).await),
+        }
+    }
+
+    /// Like [`RuntimeModule::call`], leaving the recovery to the caller.
+    ///
+    /// The outer error reports a failure to reach the guest, the inner one a
+    /// failure of the guest itself.
+    async fn invoke(
+        &self,
+        store: &mut PluginStore<P>,
+        export: &str,
+        buffer: &[u8],
+    ) -> anyhow::Result<anyhow::Result<Vec<u8>>> {
+        let (memory, alloc_fn, instance) = store
+            .data()
+            .as_ref()
+            .map(|data| (data.memory, data.alloc_fn.clone(), data.instance))
+            .context("missing plugin data")?;
+        let inner_wasm_fn = instance.get_typed_func::<u64, u64>(&mut *store, export)?;
+        store.set_epoch_deadline(self.deadline());
+        let len = buffer.len() as _;
+        let ptr = match alloc_fn.call_async(&mut *store, len).await {
+            Ok(ptr) => ptr,
+            Err(error) => return Ok(Err(error)),
+        };
+        memory.write(&mut *store, ptr as _, buffer)?;
+        let ptr = match inner_wasm_fn
+            .call_async(&mut *store, into_bitwise(ptr, len))
+            .await
+        {
+            Ok(ptr) => ptr,
+            Err(error) => return Ok(Err(error)),
+        };
+        let (ptr, len) = from_bitwise(ptr);
+        let mut buffer = vec![0u8; len as _];
+        memory.read(&mut *store, ptr as _, &mut buffer)?;
+        Ok(Ok(buffer))
+    }
+
+    /// Asks the guest for the methods its trait dispatchers can reach.
+    ///
+    /// A trait dispatcher also reaches the methods of its supertraits.
+    async fn list_methods(
+        &self,
+        store: &mut PluginStore<P>,
+    ) -> anyhow::Result<Vec<(String, String)>> {
+        let exports = self.exports_prefixed(store, METHODS_PREFIX)?;
+        let mut methods: Vec<(String, String)> = Vec::new();
+        for export in exports {
+            let output = self.invoke(store, &export, &[]).await??;
+            let dispatcher = export.replacen(METHODS_PREFIX, DISPATCH_PREFIX, 1);
+            for method in bincode::deserialize::<Vec<String>>(&output)? {
+                if !methods.iter().any(|(known, _)| *known == method) {
+                    methods.push((method, dispatcher.clone()));
+                }
+            }
+        }
+        Ok(methods)
+    }
+
+    /// Asks the guest for the signature hashes of the trait methods it was
+    /// built against.
+    async fn list_signatures(
+        &self,
+        store: &mut PluginStore<P>,
+    ) -> anyhow::Result<HashMap<String, u64>> {
+        let exports = self.exports_prefixed(store, SIGNATURES_PREFIX)?;
+        let mut signatures = HashMap::new();
+        for export in exports {
+            let output = self.invoke(store, &export, &[]).await??;
+            signatures.extend(bincode::deserialize::<Vec<(String, u64)>>(&output)?);
+        }
+        Ok(signatures)
+    }
+
+    /// The names of the guest's exports starting with `prefix`.
+    fn exports_prefixed(
+        &self,
+        store: &mut PluginStore<P>,
+        prefix: &str,
+    ) -> anyhow::Result<Vec<String>> {
+        let instance = store
+            .data()
+            .as_ref()
+            .context("missing plugin data")?
+            .instance;
+        Ok(instance
+            .exports(&mut *store)
+            .map(|export| export.name().to_owned())
+            .filter(|export| export.starts_with(prefix))
+            .collect())
+    }
+
+    /// Resolves how the plugin function `name` is called.
+    ///
+    /// Methods are called through their own export when the guest implements
+    /// them explicitly, and through their trait's dispatcher otherwise.
+    fn resolve(&self, store: &mut PluginStore<P>, name: &str) -> anyhow::Result<Route> {
+        let qualified = if name.contains("::") {
+            name.to_owned()
+        } else {
+            self.qualify(store, name)?
+        };
+        let export = format!("{GUEST_PREFIX}{qualified}");
+        if self.exports(store, &export) {
+            return Ok(Route::Export(export));
+        }
+        if let Some((_, dispatcher)) = self.methods.iter().find(|(method, _)| *method == qualified)
+        {
+            return Ok(Route::Dispatch {
+                export: dispatcher.clone(),
+                method: qualified,
+            });
+        }
+        anyhow::bail!("plugin `{}` has no function `{name}`", self.name)
+    }
+
+    /// Finds the only trait of the plugin with a method called `name`.
+    fn qualify(&self, store: &mut PluginStore<P>, name: &str) -> anyhow::Result<String> {
+        let instance = store
+            .data()
+            .as_ref()
+            .context("missing plugin data")?
+            .instance;
+        let suffix = format!("::{name}");
+        let mut candidates: Vec<String> = instance
+            .exports(&mut *store)
+            .filter_map(|export| export.name().strip_prefix(GUEST_PREFIX))
+            .chain(self.methods.iter().map(|(method, _)| method.as_str()))
+            .filter(|method| method.ends_with(&suffix))
+            .map(str::to_owned)
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+        match candidates.len() {
+            0 => anyhow::bail!("plugin `{}` has no function `{name}`", self.name),
+            1 => Ok(candidates.remove(0)),
+            _ => anyhow::bail!(
+                "function `{name}` of plugin `{}` is ambiguous, qualify it as one of {}",
+                self.name,
+                candidates.join(", ")
+            ),
+        }
+    }
+
+    /// Whether the guest exports `export`.
+    fn exports(&self, store: &mut PluginStore<P>, export: &str) -> bool {
+        store
+            .data()
+            .as_ref()
+            .map(|data| data.instance)
+            .is_some_and(|instance| instance.get_export(&mut *store, export).is_some())
+    }
+
+    /// Runs `hook` if the guest exports it, handing it a serialized input.
+    ///
+    /// Only `on_config_change` runs on an instance that keeps serving calls
+    /// afterwards, so it is the only hook whose traps poison the instance.
+    async fn hook(
+        &self,
+        store: &mut PluginStore<P>,
+        hook: Hook,
+        input: &[u8],
+    ) -> anyhow::Result<()> {
+        if !self.exports(store, hook.export()) {
+            return Ok(());
+        }
+        let output = match self.invoke(store, hook.export(), input).await? {
+            Ok(output) => output,
+            Err(error) if hook == Hook::ConfigChange => {
+                return Err(self.poison(store, error).await)
+            }
+            Err(error) => return Err(error),
+        };
+        let result: Result<(), String> = bincode::deserialize(&output)?;
+        result.map_err(|error| {
+            PluginError::Hook {
+                plugin: self.name,
+                hook: hook.name(),
+                error,
+            }
+            .into()
+        })
+    }
+
+    /// Runs the optional `on_unload` hook of the guest and drops the store.
+    async fn unload(&self, store: &mut Option<PluginStore<P>>) {
+        self.closing.store(true, Ordering::Release);
+        if let Some(live) = store.as_mut() {
+            if !self.poisoned.load(Ordering::Acquire) {
+                // `()` serializes to nothing
+                if let Err(error) = self.hook(live, Hook::Unload, &[]).await {
+                    self.events.emit(RuntimeEvent::HookFailed {
+                        plugin: self.name,
+                        hook: Hook::Unload.name(),
+                        error: format!("{error:#}"),
+                    });
+                }
+            }
+        }
+        if store.take().is_some() {
+            self.events
+                .emit(RuntimeEvent::Unloaded { plugin: self.name });
+        }
+    }
+
+    /// Drops the store of a plugin abandoned by a shutdown, unless a call
+    /// still holds it.
+    ///
+    /// The shutdown and every call finishing on the plugin try this in turn, so
+    /// that whichever is last drops the store.
+    fn release_abandoned(&self) {
+        if !self.abandoned.load(Ordering::SeqCst) {
+            return;
+        }
+        if let Some(mut store) = self.store.try_write() {
+            if store.take().is_some() {
+                self.events
+                    .emit(RuntimeEvent::Unloaded { plugin: self.name });
+            }
+        }
+    }
+
+    /// The epoch deadline applied to calls into the guest.
+    fn deadline(&self) -> u64 {
+        if self.draining.load(Ordering::Acquire) {
+            return 1;
+        }
+        self.supervisor
+            .as_ref()
+            .map_or(supervisor::NO_DEADLINE, Supervisor::deadline)
+    }
+}
+
+/// How a plugin function is reached in the guest.
+enum Route {
+    /// Through its own export.
+    Export(String),
+    /// Through the dispatcher of its trait, which is handed the method name.
+    Dispatch { export: String, method: String },
 }
 
 /// The caller of a function
-#[allow(dead_code)]
 #[derive(Clone)]
 pub struct RuntimeCaller<P> {
     pub memory: wasmtime::Memory,
     pub alloc_fn: wasmtime::TypedFunc<u32, u32>,
     pub dealloc_fn: wasmtime::TypedFunc<u64, ()>,
+    pub instance: Instance,
     pub plugin: P,
+    /// The result a synchronous context function left for the guest to take.
+    context_result: Option<Vec<u8>>,
+}
+
+impl RuntimeCaller<()> {
+    fn with_plugin<P>(self, plugin: P) -> RuntimeCaller<P> {
+        RuntimeCaller {
+            memory: self.memory,
+            alloc_fn: self.alloc_fn,
+            dealloc_fn: self.dealloc_fn,
+            instance: self.instance,
+            plugin,
+            context_result: None,
+        }
+    }
 }
 
 impl<P: std::fmt::Debug> fmt::Debug for RuntimeCaller<P> {
@@ -111,6 +820,7 @@ impl<P: std::fmt::Debug> fmt::Debug for RuntimeCaller<P> {
             .field("memory", &self.memory)
             .field("alloc_fn", &"TypedFunc<u32, u32>")
             .field("dealloc_fn", &"TypedFunc<u64, ()>")
+            .field("instance", &self.instance)
             .field("plugin", &self.plugin)
             .finish()
     }
@@ -175,31 +885,118 @@ impl<T, D: Send + 'static> Runtime<T, Plugin<D>> {
     where
         T: IntoCallable<P, D>,
     {
+        self.load_with_init(plugin, None).await
+    }
+
+    /// Loads a plugin like [`Runtime::load_with`], handing it a configuration.
+    ///
+    /// The configuration is serialized into the guest, which constructs its
+    /// plugin instance from it, see `#[plugin_impl(config = ...)]`. It is
+    /// handed over again whenever the plugin is restarted.
+    ///
+    /// ```rust
+    /// use plugy_runtime::{Plugin, Runtime};
+    /// use plugy_macros::*;
+    /// use plugy_core::PluginLoader;
+    /// use std::future::Future;
+    /// use std::pin::Pin;
+    ///
+    /// #[plugy_macros::plugin]
+    /// trait Greeter {
+    ///     fn greet(&self) -> String;
+    /// }
+    ///
+    /// #[plugin_import(file = "target/wasm32-unknown-unknown/debug/my_plugin.wasm")]
+    /// struct MyPlugin;
+    ///
+    /// impl From<MyPlugin> for Plugin {
+    ///     fn from(val: MyPlugin) -> Self {
+    ///         Plugin {
+    ///             name: "MyPlugin".to_string(),
+    ///             data: Default::default(),
+    ///             plugin_type: "MyPlugin".to_string(),
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// #[derive(serde::Serialize)]
+    /// struct GreeterConfig {
+    ///     greeting: String,
+    /// }
+    ///
+    /// async fn example(runtime: &Runtime<Box<dyn Greeter>>) {
+    ///     let config = GreeterConfig {
+    ///         greeting: "Hi".to_owned(),
+    ///     };
+    ///     let plugin = runtime.load_with_config(MyPlugin, &config).await.unwrap();
+    /// }
+    /// ```
+    pub async fn load_with_config<P, C>(&self, plugin: P, config: &C) -> anyhow::Result<T::Output>
+    where
+        P: Send + PluginLoader + Into<Plugin<D>>,
+        C: Serialize,
+        T: IntoCallable<P, D>,
+    {
+        self.load_with_init(plugin, Some(bincode::serialize(config)?))
+            .await
+    }
+
+    async fn load_with_init<P: Send + PluginLoader + Into<Plugin<D>>>(
+        &self,
+        plugin: P,
+        init: Option<Vec<u8>>,
+    ) -> anyhow::Result<T::Output>
+    where
+        T: IntoCallable<P, D>,
+    {
+        if self.closed.load(Ordering::Acquire) {
+            return Err(PluginError::Unloaded(plugin.name()).into());
+        }
         let bytes = plugin.bytes().await?;
         let name = plugin.name();
         let module = Module::new(&self.engine, bytes)?;
         let instance_pre = self.linker.instantiate_pre(&module)?;
-        let mut store: Store<Option<RuntimeCaller<Plugin<D>>>> = Store::new(&self.engine, None);
-        let instance = instance_pre.instantiate_async(&mut store).await?;
-        let memory = instance
-            .get_memory(&mut store, "memory")
-            .context("missing memory")?;
-        let alloc_fn = instance.get_typed_func(&mut store, "alloc")?;
-        let dealloc_fn = instance.get_typed_func(&mut store, "dealloc")?;
-        *store.data_mut() = Some(RuntimeCaller {
-            memory,
-            alloc_fn,
-            dealloc_fn,
-            plugin: plugin.into(),
-        });
-        self.modules.insert(
+        let (mut store, caller) = RuntimeModule::instantiate(&instance_pre).await?;
+        *store.data_mut() = Some(caller.with_plugin(plugin.into()));
+        let mut module = RuntimeModule {
             name,
-            RuntimeModule {
-                inner: module.clone(),
-                store: Arc::new(RwLock::new(store)),
-                instance,
+            loader: std::any::type_name::<P>(),
+            instance_pre,
+            store: RwLock::new(None),
+            init: Mutex::new(init),
+            methods: Vec::new(),
+            signatures: HashMap::new(),
+            poisoned: AtomicBool::new(false),
+            closing: AtomicBool::new(false),
+            draining: AtomicBool::new(false),
+            abandoned: AtomicBool::new(false),
+            recovery: self.recovery,
+            supervisor: match self.recovery {
+                RecoveryPolicy::Supervised(policy) => Some(Supervisor::new(policy)),
+                _ => None,
             },
-        );
+            events: self.events.clone(),
+        };
+        module.check_abi(&mut store).await?;
+        module.initialize(&mut store).await?;
+        module.methods = module.list_methods(&mut store).await?;
+        module.signatures = module.list_signatures(&mut store).await?;
+        module.hook(&mut store, Hook::Load, &[]).await?;
+        *module.store.get_mut() = Some(store);
+        let module = Arc::new(module);
+        // Checked again under the lock of the map, so that a shutdown running
+        // meanwhile either finds the plugin or has it refused here
+        let closed = match self.modules.entry(name) {
+            _ if self.closed.load(Ordering::Acquire) => true,
+            entry => {
+                entry.insert(module.clone());
+                false
+            }
+        };
+        if closed {
+            module.unload(&mut *module.store.write().await).await;
+            return Err(PluginError::Unloaded(name).into());
+        }
         let plugin = self.get_plugin_by_name::<P>(name)?;
         Ok(plugin)
     }
@@ -227,8 +1024,7 @@ impl<T, D: Send + 'static> Runtime<T, Plugin<D>> {
             .get(name)
             .context("missing plugin requested, did you forget .load")?;
         Ok(T::into_callable(PluginHandle {
-            store: module.store.clone(),
-            instance: module.instance,
+            module: module.clone(),
         }))
     }
 
@@ -243,18 +1039,20 @@ impl<T, D: Send + 'static> Runtime<T, Plugin<D>> {
     /// Returns a `Result` containing the callable plugin instance on success,
     /// or an `anyhow::Error` if the instance retrieval encounters any issues.
     ///
+    /// The plugin is found by the type of its loader, whatever name the loader
+    /// gave it.
     pub fn get_plugin<P: Send + PluginLoader>(&self) -> anyhow::Result<T::Output>
     where
         T: IntoCallable<P, D>,
     {
-        let name = std::any::type_name::<P>();
+        let loader = std::any::type_name::<P>();
         let module = self
             .modules
-            .get(name)
+            .iter()
+            .find(|module| module.loader == loader)
             .context("missing plugin requested, did you forget .load")?;
         Ok(T::into_callable(PluginHandle {
-            store: module.store.clone(),
-            instance: module.instance,
+            module: module.clone(),
         }))
     }
 }
@@ -315,33 +1113,7 @@ impl<T> Runtime<T> {
     where
         T: IntoCallable<P, Vec<u8>>,
     {
-        let bytes = plugin.bytes().await?;
-        let name = plugin.name();
-        let module = Module::new(&self.engine, bytes)?;
-        let instance_pre = self.linker.instantiate_pre(&module)?;
-        let mut store: Store<Option<RuntimeCaller<Plugin>>> = Store::new(&self.engine, None);
-        let instance = instance_pre.instantiate_async(&mut store).await?;
-        let memory = instance
-            .get_memory(&mut store, "memory")
-            .context("missing memory")?;
-        let alloc_fn = instance.get_typed_func(&mut store, "alloc")?;
-        let dealloc_fn = instance.get_typed_func(&mut store, "dealloc")?;
-        *store.data_mut() = Some(RuntimeCaller {
-            memory,
-            alloc_fn,
-            dealloc_fn,
-            plugin: plugin.into(),
-        });
-        self.modules.insert(
-            name,
-            RuntimeModule {
-                inner: module.clone(),
-                store: Arc::new(RwLock::new(store)),
-                instance,
-            },
-        );
-        let plugin = self.get_plugin_by_name::<P>(name)?;
-        Ok(plugin)
+        self.load_with(plugin).await
     }
 }
 
@@ -362,16 +1134,215 @@ where
     pub fn new() -> anyhow::Result<Self> {
         let mut config = wasmtime::Config::new();
         config.async_support(true);
+        // Lets supervisors interrupt calls running past their timeout
+        config.epoch_interruption(true);
         let engine = Engine::new(&config)?;
-        let linker = Linker::new(&engine);
+        let mut linker = Linker::new(&engine);
+        linker.func_wrap("env", CONTEXT_TAKE, take_context_result::<P>)?;
         let modules = DashMap::new();
         Ok(Self {
             engine,
             linker,
+            imports: HashMap::from([(("env", CONTEXT_TAKE), "plugy_runtime::Runtime")]),
             modules,
+            recovery: RecoveryPolicy::default(),
+            events: Events::default(),
+            ticking: AtomicBool::new(false),
+            closed: AtomicBool::new(false),
             structure: PhantomData,
         })
     }
+
+    /// Reads the metadata a plugin embeds without loading it, so plugins can be
+    /// listed or validated before any of their code runs.
+    ///
+    /// See [`PluginMetadata::from_bytes`].
+    pub async fn inspect<L: PluginLoader>(&self, plugin: &L) -> anyhow::Result<PluginMetadata> {
+        let bytes = plugin.bytes().await?;
+        PluginMetadata::from_bytes(&bytes)
+    }
+
+    /// Sets how plugins loaded from now on recover from traps.
+    ///
+    /// Defaults to [`RecoveryPolicy::Restart`].
+    pub fn recovery(mut self, policy: RecoveryPolicy) -> Self {
+        if let RecoveryPolicy::Supervised(SupervisorPolicy {
+            call_timeout: Some(_),
+            ..
+        }) = policy
+        {
+            self.start_ticking();
+        }
+        self.recovery = policy;
+        self
+    }
+
+    /// Starts advancing the epoch of the engine, unless it already is.
+    fn start_ticking(&self) {
+        if !self.ticking.swap(true, Ordering::AcqRel) {
+            supervisor::start_ticker(&self.engine);
+        }
+    }
+
+    /// Lifts the quarantine of a supervised plugin.
+    ///
+    /// Its failure history is cleared and it is restarted on the next call.
+    pub fn reenable(&self, name: &str) -> anyhow::Result<()> {
+        let module = self
+            .modules
+            .get(name)
+            .context("missing plugin requested, did you forget .load")?;
+        if let Some(supervisor) = &module.supervisor {
+            supervisor.reenable();
+            module.events.emit(RuntimeEvent::Reenabled {
+                plugin: module.name,
+            });
+        }
+        Ok(())
+    }
+
+    /// Whether the supervisor of the named plugin has quarantined it.
+    pub fn is_quarantined(&self, name: &str) -> bool {
+        self.modules
+            .get(name)
+            .and_then(|module| module.supervisor.as_ref().map(Supervisor::is_quarantined))
+            .unwrap_or(false)
+    }
+
+    /// Unloads the named plugin once its in-flight call is done.
+    ///
+    /// The guest's `on_unload` hook runs before its store is dropped, and
+    /// handles to the plugin fail every call from then on.
+    pub async fn unload(&self, name: &str) -> anyhow::Result<()>
+    where
+        P: Send,
+    {
+        let (_, module) = self
+            .modules
+            .remove(name)
+            .context("missing plugin requested, did you forget .load")?;
+        module.closing.store(true, Ordering::Release);
+        module.unload(&mut *module.store.write().await).await;
+        Ok(())
+    }
+
+    /// Hands a new configuration to the named plugin through its
+    /// `on_config_change` hook.
+    ///
+    /// Plugins without the hook ignore the configuration. An error reported by
+    /// the hook is returned as [`PluginError::Hook`]. Once accepted, the
+    /// configuration replaces the one a plugin was loaded with through
+    /// [`Runtime::load_with_config`], and an instance restarted after a trap is
+    /// constructed from it. Plugins loaded without a config are still
+    /// constructed with `Default`.
+    pub async fn reconfigure<C: Serialize>(&self, name: &str, config: &C) -> anyhow::Result<()>
+    where
+        P: Send,
+    {
+        let module = self
+            .modules
+            .get(name)
+            .map(|module| module.value().clone())
+            .context("missing plugin requested, did you forget .load")?;
+        let input = bincode::serialize(config)?;
+        let result = async {
+            let mut store = module.store.write().await;
+            let store = module.live(&mut store)?;
+            module.ensure_healthy(store).await?;
+            module.hook(store, Hook::ConfigChange, &input).await?;
+            // Restarts construct the instance from the new config, unless it
+            // was constructed without one
+            if let Some(init) = module
+                .init
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .as_mut()
+            {
+                *init = input;
+            }
+            Ok(())
+        }
+        .await;
+        module.release_abandoned();
+        result
+    }
+
+    /// Shuts the runtime down, draining in-flight calls first.
+    ///
+    /// New loads and calls are refused right away with [`PluginError::Unloaded`].
+    /// Each plugin then waits for its in-flight call to finish, runs the guest's
+    /// `on_unload` hook if it has one, and has its store dropped.
+    /// Plugins still busy once `timeout` has elapsed, in a call or in their
+    /// `on_unload` hook, are abandoned and listed in the returned error. Their
+    /// store is dropped as soon as the call holding it is done.
+    pub async fn shutdown(&self, timeout: Duration) -> anyhow::Result<()>
+    where
+        P: Send,
+    {
+        self.closed.store(true, Ordering::Release);
+        let modules: Vec<_> = self
+            .modules
+            .iter()
+            .map(|module| module.value().clone())
+            .collect();
+        for module in &modules {
+            module.closing.store(true, Ordering::Release);
+        }
+        // Lets hooks spinning in the guest yield to the deadline
+        self.start_ticking();
+        let deadline = Instant::now() + timeout;
+        let mut abandoned = Vec::new();
+        for module in modules {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let unload = async {
+                let mut store = module.store.write().await;
+                if let Some(live) = store.as_mut() {
+                    module.draining.store(true, Ordering::Release);
+                    live.epoch_deadline_async_yield_and_update(1);
+                }
+                module.unload(&mut store).await;
+            };
+            if timer::timeout(remaining, unload).await.is_none() {
+                module.abandoned.store(true, Ordering::SeqCst);
+                module.release_abandoned();
+                abandoned.push(module.name);
+            }
+            self.modules.remove(module.name);
+        }
+        if abandoned.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "plugins still busy after {timeout:?}: {}",
+                abandoned.join(", ")
+            ))
+        }
+    }
+
+    /// Registers a listener notified of every [`RuntimeEvent`].
+    ///
+    /// ```rust
+    /// use plugy_runtime::{Runtime, RuntimeEvent};
+    ///
+    /// trait Greeter {
+    ///     fn greet(&self);
+    /// }
+    /// let runtime = Runtime::<Box<dyn Greeter>>::new()
+    ///     .unwrap()
+    ///     .on_event(|event| {
+    ///         if let RuntimeEvent::Restarted { plugin } = event {
+    ///             println!("{plugin} was restarted");
+    ///         }
+    ///     });
+    /// ```
+    pub fn on_event(self, listener: impl Fn(&RuntimeEvent) + Send + Sync + 'static) -> Self {
+        self.events
+            .0
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(Arc::new(listener));
+        self
+    }
 }
 
 impl<T, D> Runtime<T, Plugin<D>> {
@@ -396,17 +1367,48 @@ impl<T, D> Runtime<T, Plugin<D>> {
     /// let runtime = runtime
     ///     .context(Logger);
     /// ````
-    pub fn context<C: Context<D>>(mut self, ctx: C) -> Self {
-        ctx.link(&mut self.linker);
-        self
+    ///
+    /// # Panics
+    ///
+    /// Panics when the context defines a function another context already
+    /// linked, or fails to link, see [`Runtime::try_context`].
+    pub fn context<C: Context<D>>(self, ctx: C) -> Self {
+        self.try_context(ctx).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Links a context like [`Runtime::context`], failing with an
+    /// [`ImportCollision`] when one of its functions is already defined under
+    /// the same module and name, or with the error of the linker.
+    pub fn try_context<C: Context<D>>(mut self, ctx: C) -> anyhow::Result<Self> {
+        let context = std::any::type_name::<C>();
+        let imports = ctx.imports();
+        for (i, &(module, name)) in imports.iter().enumerate() {
+            let first = self
+                .imports
+                .get(&(module, name))
+                .copied()
+                .or_else(|| imports[..i].contains(&(module, name)).then_some(context));
+            if let Some(first) = first {
+                return Err(ImportCollision {
+                    module,
+                    name,
+                    first,
+                    second: context,
+                }
+                .into());
+            }
+        }
+        self.imports
+            .extend(imports.into_iter().map(|import| (import, context)));
+        ctx.link(&mut self.linker)?;
+        Ok(self)
     }
 }
 
 /// A handle to a loaded plugin instance.
 ///
 /// This struct represents a handle to a loaded plugin instance. It holds a reference
-/// to the underlying instance, along with a reference to the associated store and
-/// any additional data (`PhantomData<P>`) specific to the plugin type `P`.
+/// to the shared module, which owns the underlying instance and its store.
 ///
 /// # Type Parameters
 ///
@@ -417,11 +1419,10 @@ pub struct PluginHandle<P = Plugin>
 where
     P: 'static,
 {
-    instance: Instance,
-    store: CallerStore<P>,
+    module: Arc<RuntimeModule<P>>,
 }
 
-impl<D> PluginHandle<Plugin<D>> {
+impl<D: Send> PluginHandle<Plugin<D>> {
     /// Retrieves a typed function interface from the loaded plugin instance.
     ///
     /// This method enables retrieving a typed function interface for a specific
@@ -431,7 +1432,9 @@ impl<D> PluginHandle<Plugin<D>> {
     ///
     /// # Parameters
     ///
-    /// - `name`: The name of the function in the plugin instance.
+    /// - `name`: The name of the function in the plugin instance, either
+    ///   qualified with its trait as in `Greeter::greet`, or bare as in `greet`
+    ///   when no other trait of the plugin has a method of that name.
     ///
     /// # Type Parameters
     ///
@@ -446,31 +1449,85 @@ impl<D> PluginHandle<Plugin<D>> {
         &self,
         name: &str,
     ) -> anyhow::Result<Func<Plugin<D>, I, R>> {
-        let store = self.store.clone();
-        let inner_wasm_fn = self.instance.get_typed_func::<u64, u64>(
-            &mut *store.write().await,
-            &format!("_plugy_guest_{name}"),
-        )?;
+        let mut store = self.module.store.write().await;
+        let store = self.module.live(&mut store)?;
+        let (export, method) = match self.module.resolve(store, name)? {
+            Route::Export(export) => (export, None),
+            Route::Dispatch { export, method } => (export, Some(method)),
+        };
+        let instance = store
+            .data()
+            .as_ref()
+            .context("missing plugin data")?
+            .instance;
+        instance.get_typed_func::<u64, u64>(&mut *store, &export)?;
         Ok(Func {
-            inner_wasm_fn,
-            store,
+            module: self.module.clone(),
+            export,
+            method,
             input: std::marker::PhantomData::<I>,
             output: std::marker::PhantomData::<R>,
         })
     }
+
+    /// Retrieves a typed function interface like [`PluginHandle::get_func`],
+    /// after checking that the plugin was built against the same signature of
+    /// the trait method.
+    ///
+    /// `signature` is the hash `#[plugin]` computes from the argument and return
+    /// types of the method, which the wrappers it generates pass along. Guests
+    /// listing no signature for the method, such as those built before
+    /// signatures were exported, are not checked.
+    pub async fn get_func_with_signature<I: Serialize, R: DeserializeOwned>(
+        &self,
+        name: &str,
+        signature: u64,
+    ) -> anyhow::Result<Func<Plugin<D>, I, R>> {
+        let func = self.get_func(name).await?;
+        let method = match &func.method {
+            Some(method) => method.as_str(),
+            None => func.export.trim_start_matches(GUEST_PREFIX),
+        };
+        match self.module.signatures.get(method) {
+            Some(expected) if *expected != signature => Err(PluginError::SignatureMismatch {
+                plugin: self.module.name,
+                method: method.to_owned(),
+            }
+            .into()),
+            _ => Ok(func),
+        }
+    }
+
+    /// Whether the plugin implements the function `name`, either explicitly or
+    /// through the default body of its trait method.
+    ///
+    /// Names are resolved as in [`PluginHandle::get_func`].
+    pub async fn has_method(&self, name: &str) -> bool {
+        let mut store = self.module.store.write().await;
+        match self.module.live(&mut store) {
+            Ok(store) => self.module.resolve(store, name).is_ok(),
+            Err(_) => false,
+        }
+    }
 }
 
+/// A callable function exported by a plugin.
+///
+/// The export is resolved against the current instance on every call, so a
+/// `Func` keeps working after its plugin has been restarted.
 pub struct Func<P, I: Serialize, R: DeserializeOwned>
 where
     P: 'static,
 {
-    inner_wasm_fn: wasmtime::TypedFunc<u64, u64>,
-    store: CallerStore<P>,
+    module: Arc<RuntimeModule<P>>,
+    export: String,
+    /// The method handed to a trait dispatcher along with the input.
+    method: Option<String>,
     input: PhantomData<I>,
     output: PhantomData<R>,
 }
 
-impl<P: Send + Clone, R: DeserializeOwned, I: Serialize> Func<P, I, R> {
+impl<P: Send, R: DeserializeOwned, I: Serialize> Func<P, I, R> {
     /// Invokes the plugin function with the provided input, returning the result.
     ///
     /// This asynchronous method calls the plugin function using the provided input data
@@ -502,27 +1559,946 @@ impl<P: Send + Clone, R: DeserializeOwned, I: Serialize> Func<P, I, R> {
     /// Returns a `Result` containing the result of the plugin function call on success,
     /// or an `anyhow::Error` if the function call or deserialization encounters issues.
     pub async fn call_checked(&self, value: &I) -> anyhow::Result<R> {
-        let mut store = self.store.write().await;
-        let data = store.data_mut().clone().unwrap();
-        let RuntimeCaller {
-            memory, alloc_fn, ..
-        } = data;
-
-        let buffer = bincode::serialize(value)?;
-        let len = buffer.len() as _;
-        let ptr = alloc_fn.call_async(&mut *store, len).await?;
-        memory.write(&mut *store, ptr as _, &buffer)?;
-        let ptr = self
-            .inner_wasm_fn
-            .call_async(&mut *store, into_bitwise(ptr, len))
-            .await?;
-        let (ptr, len) = from_bitwise(ptr);
-        let mut buffer = vec![0u8; len as _];
-        memory.read(&mut *store, ptr as _, &mut buffer)?;
-        Ok(bincode::deserialize(&buffer)?)
+        let buffer = match &self.method {
+            Some(method) => bincode::serialize(&(method, value))?,
+            None => bincode::serialize(value)?,
+        };
+        let output = async {
+            let mut store = self.module.store.write().await;
+            let store = self.module.live(&mut store)?;
+            self.module.ensure_healthy(store).await?;
+            self.module.call(store, &self.export, &buffer).await
+        }
+        .await;
+        self.module.release_abandoned();
+        let output = output?;
+        if let Some(supervisor) = &self.module.supervisor {
+            supervisor.record_success();
+        }
+        Ok(bincode::deserialize(&output)?)
     }
 }
 
 pub trait Context<D = Vec<u8>>: Sized {
-    fn link(&self, linker: &mut Linker<Plugin<D>>);
+    /// The `(module, name)` of every function `link` defines, which
+    /// [`Runtime::try_context`] checks against the other contexts.
+    fn imports(&self) -> Vec<(&'static str, &'static str)>;
+
+    /// Defines the functions of the context in `linker`.
+    fn link(&self, linker: &mut Linker<Plugin<D>>) -> anyhow::Result<()>;
+}
+
+/// Two contexts define the same function, reported by [`Runtime::try_context`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportCollision {
+    pub module: &'static str,
+    pub name: &'static str,
+    /// The context that defined the function first.
+    pub first: &'static str,
+    /// The context that was being linked.
+    pub second: &'static str,
+}
+
+impl fmt::Display for ImportCollision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "context `{}` defines `{}::{}`, already defined by `{}`",
+            self.second, self.module, self.name, self.first
+        )
+    }
+}
+
+impl std::error::Error for ImportCollision {}
+
+/// A built-in context giving guests access to their [`Plugin::data`] blob
+/// through `plugy_core::guest::{data, set_data, update_data}`.
+///
+/// ```rust
+/// use plugy_runtime::{PluginData, Runtime};
+///
+/// trait Greeter {
+///     fn greet(&self);
+/// }
+/// let runtime = Runtime::<Box<dyn Greeter>>::new()
+///     .unwrap()
+///     .context(PluginData);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PluginData;
+
+/// The import module of the [`PluginData`] functions.
+const PLUGIN_DATA: &str = "plugy:plugin_data";
+
+type ContextFuture<'a> = Box<dyn std::future::Future<Output = anyhow::Result<u64>> + Send + 'a>;
+
+impl Context for PluginData {
+    fn imports(&self) -> Vec<(&'static str, &'static str)> {
+        vec![(PLUGIN_DATA, "get"), (PLUGIN_DATA, "set")]
+    }
+
+    fn link(&self, linker: &mut Linker) -> anyhow::Result<()> {
+        linker.func_wrap_async(
+            PLUGIN_DATA,
+            "get",
+            |mut caller: Caller<'_, Plugin>, (ptr,): (u64,)| -> ContextFuture<'_> {
+                Box::new(async move {
+                    let result = read_context_args(&mut caller, ptr).await.map(|_| {
+                        caller
+                            .data()
+                            .as_ref()
+                            .map(|data| data.plugin.data.clone())
+                            .unwrap_or_default()
+                    });
+                    write_context_result(&mut caller, result).await
+                })
+            },
+        )?;
+        linker.func_wrap_async(
+            PLUGIN_DATA,
+            "set",
+            |mut caller: Caller<'_, Plugin>, (ptr,): (u64,)| -> ContextFuture<'_> {
+                Box::new(async move {
+                    let result = match read_context_args(&mut caller, ptr).await {
+                        Ok(buffer) => deserialize_context_args::<Vec<u8>>(&buffer),
+                        Err(e) => Err(e),
+                    }
+                    .map(|blob| {
+                        if let Some(data) = caller.data_mut() {
+                            data.plugin.data = blob;
+                        }
+                    });
+                    write_context_result(&mut caller, result).await
+                })
+            },
+        )?;
+        Ok(())
+    }
+}
+
+/// Reads the serialized arguments of a context call from the guest memory.
+///
+/// The guest buffer is released once it has been copied. Failures are returned
+/// as a [`ContextError`] so that they can be reported back to the guest instead
+/// of bringing down the host.
+pub async fn read_context_args<D: Send>(
+    caller: &mut Caller<'_, Plugin<D>>,
+    value: u64,
+) -> Result<Vec<u8>, ContextError> {
+    let (memory, dealloc_fn) = caller
+        .data()
+        .as_ref()
+        .map(|data| (data.memory, data.dealloc_fn.clone()))
+        .ok_or_else(|| ContextError::Memory("plugin is not initialized".to_owned()))?;
+    let (ptr, len) = from_bitwise(value);
+    let mut buffer = vec![0u8; len as _];
+    memory
+        .read(&mut *caller, ptr as _, &mut buffer)
+        .map_err(|e| ContextError::Memory(e.to_string()))?;
+    dealloc_fn
+        .call_async(&mut *caller, value)
+        .await
+        .map_err(|e| ContextError::Memory(e.to_string()))?;
+    Ok(buffer)
+}
+
+/// Deserializes the arguments of a context call read by [`read_context_args`].
+pub fn deserialize_context_args<'a, A: serde::Deserialize<'a>>(
+    buffer: &'a [u8],
+) -> Result<A, ContextError> {
+    bincode::deserialize(buffer).map_err(|e| ContextError::Deserialize(e.to_string()))
+}
+
+/// Writes the result envelope of a context call into the guest memory.
+///
+/// The guest always receives a `Result<R, ContextError>`. An error is only
+/// returned here if the envelope itself cannot be handed over (the guest
+/// allocator or memory failed), in which case the guest call traps.
+pub async fn write_context_result<D: Send, R: Serialize>(
+    caller: &mut Caller<'_, Plugin<D>>,
+    result: Result<R, ContextError>,
+) -> anyhow::Result<u64> {
+    let buffer = serialize_context_result(result)?;
+    let (memory, alloc_fn) = caller
+        .data()
+        .as_ref()
+        .map(|data| (data.memory, data.alloc_fn.clone()))
+        .context("plugin is not initialized")?;
+    let ptr = alloc_fn.call_async(&mut *caller, buffer.len() as _).await?;
+    memory.write(&mut *caller, ptr as _, &buffer)?;
+    Ok(into_bitwise(ptr, buffer.len() as _))
+}
+
+fn serialize_context_result<R: Serialize>(
+    result: Result<R, ContextError>,
+) -> anyhow::Result<Vec<u8>> {
+    match bincode::serialize(&result) {
+        Ok(buffer) => Ok(buffer),
+        Err(e) => Ok(bincode::serialize(&Err::<(), _>(ContextError::Serialize(
+            e.to_string(),
+        )))?),
+    }
+}
+
+/// Reads the serialized arguments of a synchronous context call from the guest
+/// memory.
+///
+/// Synchronous host functions cannot call into the guest, so unlike
+/// [`read_context_args`] the buffer is left for the guest to release.
+pub fn read_sync_context_args<D>(
+    caller: &mut Caller<'_, Plugin<D>>,
+    value: u64,
+) -> Result<Vec<u8>, ContextError> {
+    let memory = caller
+        .data()
+        .as_ref()
+        .map(|data| data.memory)
+        .ok_or_else(|| ContextError::Memory("plugin is not initialized".to_owned()))?;
+    let (ptr, len) = from_bitwise(value);
+    let mut buffer = vec![0u8; len as _];
+    memory
+        .read(&mut *caller, ptr as _, &mut buffer)
+        .map_err(|e| ContextError::Memory(e.to_string()))?;
+    Ok(buffer)
+}
+
+/// Keeps the result envelope of a synchronous context call until the guest
+/// allocated room for it and takes it, returning its length.
+pub fn stash_context_result<D, R: Serialize>(
+    caller: &mut Caller<'_, Plugin<D>>,
+    result: Result<R, ContextError>,
+) -> anyhow::Result<u64> {
+    let buffer = serialize_context_result(result)?;
+    let len = buffer.len() as u64;
+    let data = caller
+        .data_mut()
+        .as_mut()
+        .context("plugin is not initialized")?;
+    data.context_result = Some(buffer);
+    Ok(len)
+}
+
+/// Writes the result stashed by [`stash_context_result`] into the buffer the
+/// guest allocated for it.
+fn take_context_result<P>(mut caller: Caller<'_, P>, value: u64) -> anyhow::Result<()> {
+    let data = caller
+        .data_mut()
+        .as_mut()
+        .context("plugin is not initialized")?;
+    let buffer = data
+        .context_result
+        .take()
+        .context("no context result to take")?;
+    let memory = data.memory;
+    let (ptr, len) = from_bitwise(value);
+    anyhow::ensure!(
+        buffer.len() == len as usize,
+        "the context result takes {} bytes, not {len}",
+        buffer.len()
+    );
+    memory.write(&mut caller, ptr as _, &buffer)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::Mutex;
+
+    /// A hand written guest exporting `count`, which increments a global,
+    /// `trap`, which always traps, and `spin`, which never returns.
+    const COUNTER: &str = r#"
+        (module
+            (memory (export "memory") 1)
+            (global $heap (mut i32) (i32.const 1024))
+            (global $count (mut i64) (i64.const 0))
+            (global $abi i32 (i32.const 1))
+            (func (export "_plugy_abi_version") (result i32)
+                (global.get $abi))
+            (func (export "alloc") (param $len i32) (result i32)
+                (local $ptr i32)
+                (local.set $ptr (global.get $heap))
+                (global.set $heap (i32.add (global.get $heap) (local.get $len)))
+                (local.get $ptr))
+            (func (export "dealloc") (param i64))
+            (func (export "_plugy_guest_Counter::count") (param i64) (result i64)
+                (global.set $count (i64.add (global.get $count) (i64.const 1)))
+                (i64.store (i32.const 0) (global.get $count))
+                ;; 8 bytes at offset 0
+                (i64.const 0x800000000))
+            (func (export "_plugy_guest_Counter::trap") (param i64) (result i64)
+                unreachable)
+            (func (export "_plugy_guest_Counter::spin") (param i64) (result i64)
+                (loop $spin (br $spin))
+                unreachable))
+    "#;
+
+    /// [`COUNTER`] with extra functions spliced in, such as hooks.
+    fn counter_with(funcs: &str) -> &'static str {
+        let (head, tail) = COUNTER.rsplit_once("))").unwrap();
+        format!("{head}){funcs}){tail}").leak()
+    }
+
+    struct Wat(&'static str);
+
+    impl PluginLoader for Wat {
+        fn bytes(&self) -> Pin<Box<dyn Future<Output = Result<Vec<u8>, anyhow::Error>>>> {
+            let bytes = self.0.as_bytes().to_vec();
+            Box::pin(async move { Ok(bytes) })
+        }
+
+        fn name(&self) -> &'static str {
+            "wat"
+        }
+    }
+
+    impl From<Wat> for Plugin {
+        fn from(_: Wat) -> Self {
+            Plugin {
+                name: "wat".to_owned(),
+                plugin_type: "wat".to_owned(),
+                data: vec![42],
+            }
+        }
+    }
+
+    struct Raw;
+
+    impl<P> IntoCallable<P, Vec<u8>> for Raw {
+        type Output = PluginHandle;
+        fn into_callable(handle: PluginHandle) -> Self::Output {
+            handle
+        }
+    }
+
+    fn recorder() -> (
+        Arc<Mutex<Vec<RuntimeEvent>>>,
+        impl Fn(&RuntimeEvent) + Send + Sync,
+    ) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        (events, move |event: &RuntimeEvent| {
+            sink.lock().unwrap().push(event.clone())
+        })
+    }
+
+    #[tokio::test]
+    async fn restarts_after_trap() {
+        let (events, listener) = recorder();
+        let runtime = Runtime::<Raw>::new().unwrap().on_event(listener);
+        let handle = runtime.load(Wat(COUNTER)).await.unwrap();
+        let count = handle.get_func::<(), u64>("count").await.unwrap();
+        let trap = handle.get_func::<(), u64>("trap").await.unwrap();
+        assert_eq!(count.call_checked(&()).await.unwrap(), 1);
+        assert_eq!(count.call_checked(&()).await.unwrap(), 2);
+        assert!(trap.call_checked(&()).await.is_err());
+        // The instance was replaced, its globals start over
+        assert_eq!(count.call_checked(&()).await.unwrap(), 1);
+        assert!(matches!(
+            &events.lock().unwrap()[..],
+            [
+                RuntimeEvent::Poisoned { plugin: "wat", .. },
+                RuntimeEvent::Restarted { plugin: "wat" }
+            ]
+        ));
+        let store = handle.module.store.read().await;
+        let store = store.as_ref().unwrap();
+        assert_eq!(store.data().as_ref().unwrap().plugin.data, vec![42]);
+    }
+
+    #[tokio::test]
+    async fn stays_poisoned() {
+        let runtime = Runtime::<Raw>::new()
+            .unwrap()
+            .recovery(RecoveryPolicy::Poison);
+        let handle = runtime.load(Wat(COUNTER)).await.unwrap();
+        let count = handle.get_func::<(), u64>("count").await.unwrap();
+        let trap = handle.get_func::<(), u64>("trap").await.unwrap();
+        assert!(trap.call_checked(&()).await.is_err());
+        let error = count.call_checked(&()).await.unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<PluginError>(),
+            Some(&PluginError::Poisoned("wat"))
+        );
+    }
+
+    fn supervised(policy: SupervisorPolicy) -> Runtime<Raw> {
+        Runtime::<Raw>::new()
+            .unwrap()
+            .recovery(RecoveryPolicy::Supervised(policy))
+    }
+
+    #[tokio::test]
+    async fn quarantines_after_repeated_failures() {
+        let (events, listener) = recorder();
+        let runtime = supervised(SupervisorPolicy {
+            max_failures: 2,
+            backoff: Backoff {
+                initial: Duration::ZERO,
+                ..Backoff::default()
+            },
+            ..SupervisorPolicy::default()
+        })
+        .on_event(listener);
+        let handle = runtime.load(Wat(COUNTER)).await.unwrap();
+        let count = handle.get_func::<(), u64>("count").await.unwrap();
+        let trap = handle.get_func::<(), u64>("trap").await.unwrap();
+        assert!(trap.call_checked(&()).await.is_err());
+        assert_eq!(count.call_checked(&()).await.unwrap(), 1);
+        assert!(trap.call_checked(&()).await.is_err());
+        assert!(runtime.is_quarantined("wat"));
+        let error = count.call_checked(&()).await.unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<PluginError>(),
+            Some(&PluginError::Quarantined("wat"))
+        );
+        assert!(events.lock().unwrap().contains(&RuntimeEvent::Quarantined {
+            plugin: "wat",
+            failures: 2
+        }));
+        runtime.reenable("wat").unwrap();
+        assert!(!runtime.is_quarantined("wat"));
+        assert_eq!(count.call_checked(&()).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn backs_off_before_restarting() {
+        let runtime = supervised(SupervisorPolicy {
+            backoff: Backoff {
+                initial: Duration::from_millis(50),
+                ..Backoff::default()
+            },
+            ..SupervisorPolicy::default()
+        });
+        let handle = runtime.load(Wat(COUNTER)).await.unwrap();
+        let count = handle.get_func::<(), u64>("count").await.unwrap();
+        let trap = handle.get_func::<(), u64>("trap").await.unwrap();
+        assert!(trap.call_checked(&()).await.is_err());
+        let error = count.call_checked(&()).await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<PluginError>(),
+            Some(PluginError::BackingOff { plugin: "wat", .. })
+        ));
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(count.call_checked(&()).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn interrupts_calls_past_their_timeout() {
+        let runtime = supervised(SupervisorPolicy {
+            backoff: Backoff {
+                initial: Duration::ZERO,
+                ..Backoff::default()
+            },
+            call_timeout: Some(Duration::from_millis(50)),
+            ..SupervisorPolicy::default()
+        });
+        let handle = runtime.load(Wat(COUNTER)).await.unwrap();
+        let spin = handle.get_func::<(), u64>("spin").await.unwrap();
+        let error = spin.call_checked(&()).await.unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<PluginError>(),
+            Some(&PluginError::TimedOut("wat"))
+        );
+        let count = handle.get_func::<(), u64>("count").await.unwrap();
+        assert_eq!(count.call_checked(&()).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn shutdown_runs_unload_hook() {
+        let (events, listener) = recorder();
+        let runtime = Runtime::<Raw>::new().unwrap().on_event(listener);
+        let guest = counter_with(
+            r#"(func (export "_plugy_hook_on_unload") (param i64) (result i64)
+                unreachable)"#,
+        );
+        let handle = runtime.load(Wat(guest)).await.unwrap();
+        let count = handle.get_func::<(), u64>("count").await.unwrap();
+        assert_eq!(count.call_checked(&()).await.unwrap(), 1);
+        runtime.shutdown(Duration::from_secs(1)).await.unwrap();
+        assert!(matches!(
+            &events.lock().unwrap()[..],
+            [
+                RuntimeEvent::HookFailed {
+                    plugin: "wat",
+                    hook: "on_unload",
+                    ..
+                },
+                RuntimeEvent::Unloaded { plugin: "wat" }
+            ]
+        ));
+        let error = count.call_checked(&()).await.unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<PluginError>(),
+            Some(&PluginError::Unloaded("wat"))
+        );
+        let error = runtime.load(Wat(COUNTER)).await.unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<PluginError>(),
+            Some(&PluginError::Unloaded("wat"))
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn shutdown_abandons_busy_plugins() {
+        let (events, listener) = recorder();
+        let runtime = supervised(SupervisorPolicy {
+            call_timeout: Some(Duration::from_millis(500)),
+            ..SupervisorPolicy::default()
+        })
+        .on_event(listener);
+        let handle = runtime.load(Wat(COUNTER)).await.unwrap();
+        let spin = handle.get_func::<(), u64>("spin").await.unwrap();
+        let call = tokio::spawn(async move { spin.call_checked(&()).await });
+        // The spinning call may hold up tokio's timers along with its worker,
+        // so wait for it to take the store rather than sleeping
+        while handle.module.store.try_write().is_some() {
+            tokio::task::yield_now().await;
+        }
+        let error = runtime
+            .shutdown(Duration::from_millis(50))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("wat"));
+        assert!(call.await.unwrap().is_err());
+        // The call dropped the store on its way out
+        assert!(handle.module.store.read().await.is_none());
+        assert!(events
+            .lock()
+            .unwrap()
+            .contains(&RuntimeEvent::Unloaded { plugin: "wat" }));
+    }
+
+    #[tokio::test]
+    async fn shutdown_bounds_the_unload_hook() {
+        let runtime = Runtime::<Raw>::new().unwrap();
+        let guest = counter_with(
+            r#"(func (export "_plugy_hook_on_unload") (param i64) (result i64)
+                (loop $spin (br $spin))
+                unreachable)"#,
+        );
+        let handle = runtime.load(Wat(guest)).await.unwrap();
+        let error = runtime
+            .shutdown(Duration::from_millis(50))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("wat"));
+        assert!(handle.module.store.read().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn on_load_errors_fail_the_load() {
+        let runtime = Runtime::<Raw>::new().unwrap();
+        // `Err("no")` at offset 32
+        let guest = counter_with(
+            r#"(data (i32.const 32) "\01\00\00\00\02\00\00\00\00\00\00\00no")
+            (func (export "_plugy_hook_on_load") (param i64) (result i64)
+                (i64.const 0xe00000020))"#,
+        );
+        let error = runtime.load(Wat(guest)).await.unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<PluginError>(),
+            Some(&PluginError::Hook {
+                plugin: "wat",
+                hook: "on_load",
+                error: "no".to_owned()
+            })
+        );
+        assert!(runtime.get_plugin_by_name::<Wat>("wat").is_err());
+    }
+
+    #[tokio::test]
+    async fn finds_plugins_by_loader_type() {
+        let runtime = Runtime::<Raw>::new().unwrap();
+        assert!(runtime.get_plugin::<Wat>().is_err());
+        runtime.load(Wat(COUNTER)).await.unwrap();
+        // `Wat` names its plugins "wat" rather than after its type
+        let handle = runtime.get_plugin::<Wat>().unwrap();
+        assert_eq!(handle.module.name, "wat");
+    }
+
+    #[tokio::test]
+    async fn constructs_instances_at_load() {
+        let runtime = Runtime::<Raw>::new().unwrap();
+        // `Ok(())` at offset 16, construction starts the count at 10
+        let guest = counter_with(
+            r#"(func (export "_plugy_construct_Counter") (param i64) (result i64)
+                (global.set $count (i64.const 10))
+                (i64.const 0x400000010))"#,
+        );
+        let handle = runtime.load(Wat(guest)).await.unwrap();
+        let count = handle.get_func::<(), u64>("count").await.unwrap();
+        assert_eq!(count.call_checked(&()).await.unwrap(), 11);
+
+        // `Err("no")` at offset 32
+        let guest = counter_with(
+            r#"(data (i32.const 32) "\01\00\00\00\02\00\00\00\00\00\00\00no")
+            (func (export "_plugy_construct_Counter") (param i64) (result i64)
+                (i64.const 0xe00000020))"#,
+        );
+        let runtime = Runtime::<Raw>::new().unwrap();
+        let error = runtime.load(Wat(guest)).await.unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<PluginError>(),
+            Some(&PluginError::Construct {
+                plugin: "wat",
+                error: "no".to_owned()
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn reconfigures_and_unloads() {
+        let (events, listener) = recorder();
+        let runtime = Runtime::<Raw>::new().unwrap().on_event(listener);
+        // `Ok(())` at offset 16, the config lands in the `count` global
+        let guest = counter_with(
+            r#"(func (export "_plugy_hook_on_config_change") (param $value i64) (result i64)
+                (global.set $count (i64.load (i32.wrap_i64 (local.get $value))))
+                (i64.const 0x400000010))"#,
+        );
+        let handle = runtime.load(Wat(guest)).await.unwrap();
+        let count = handle.get_func::<(), u64>("count").await.unwrap();
+        runtime.reconfigure("wat", &41u64).await.unwrap();
+        assert_eq!(count.call_checked(&()).await.unwrap(), 42);
+        runtime.unload("wat").await.unwrap();
+        assert!(count.call_checked(&()).await.is_err());
+        assert_eq!(
+            &events.lock().unwrap()[..],
+            [RuntimeEvent::Unloaded { plugin: "wat" }]
+        );
+    }
+
+    #[tokio::test]
+    async fn restarts_unconfigured_plugins_without_the_config() {
+        let runtime = Runtime::<Raw>::new().unwrap();
+        // The count starts at the length of the init bytes, which a plugin
+        // type with fields would be decoded from
+        let guest = counter_with(
+            r#"(func (export "_plugy_init") (param $value i64) (result i64)
+                (global.set $count (i64.shr_u (local.get $value) (i64.const 32)))
+                (i64.const 0))
+            (func (export "_plugy_hook_on_config_change") (param i64) (result i64)
+                (i64.const 0x400000010))"#,
+        );
+        let handle = runtime.load(Wat(guest)).await.unwrap();
+        let count = handle.get_func::<(), u64>("count").await.unwrap();
+        let trap = handle.get_func::<(), u64>("trap").await.unwrap();
+        runtime.reconfigure("wat", &"hi".to_owned()).await.unwrap();
+        assert_eq!(count.call_checked(&()).await.unwrap(), 1);
+        assert!(trap.call_checked(&()).await.is_err());
+        assert_eq!(count.call_checked(&()).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn checks_the_abi_version() {
+        let runtime = Runtime::<Raw>::new().unwrap();
+        runtime.load(Wat(COUNTER)).await.unwrap();
+        let unsupported = COUNTER.replace(
+            "(global $abi i32 (i32.const 1))",
+            "(global $abi i32 (i32.const 99))",
+        );
+        let Err(error) = runtime.load(Wat(unsupported.leak())).await else {
+            panic!("ABI version 99 is not supported");
+        };
+        assert_eq!(
+            error.downcast_ref::<PluginError>(),
+            Some(&PluginError::IncompatibleAbi {
+                plugin: "wat",
+                version: 99,
+            })
+        );
+        // Guests built before the version was exported predate the context
+        // result envelopes
+        let (head, tail) = COUNTER
+            .split_once("(func (export \"_plugy_abi_version\")")
+            .unwrap();
+        let (_, tail) = tail.split_once("(global.get $abi))").unwrap();
+        let Err(error) = runtime.load(Wat(format!("{head}{tail}").leak())).await else {
+            panic!("guests without an ABI version are not supported");
+        };
+        assert_eq!(
+            error.downcast_ref::<PluginError>(),
+            Some(&PluginError::IncompatibleAbi {
+                plugin: "wat",
+                version: 0,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn initializes_fresh_instances() {
+        let runtime = Runtime::<Raw>::new().unwrap();
+        let guest = counter_with(
+            r#"(func (export "_plugy_init") (param i64) (result i64)
+                (global.set $count (i64.const 100))
+                (i64.const 0))"#,
+        );
+        let handle = runtime.load(Wat(guest)).await.unwrap();
+        let count = handle.get_func::<(), u64>("count").await.unwrap();
+        let trap = handle.get_func::<(), u64>("trap").await.unwrap();
+        assert_eq!(count.call_checked(&()).await.unwrap(), 101);
+        assert_eq!(count.call_checked(&()).await.unwrap(), 102);
+        assert!(trap.call_checked(&()).await.is_err());
+        assert_eq!(count.call_checked(&()).await.unwrap(), 101);
+    }
+
+    #[tokio::test]
+    async fn hands_the_config_to_the_guest() {
+        let runtime = Runtime::<Raw>::new().unwrap();
+        let guest = counter_with(
+            r#"(func (export "_plugy_init") (param $value i64) (result i64)
+                (global.set $count (i64.load (i32.wrap_i64 (local.get $value))))
+                (i64.const 0))"#,
+        );
+        let handle = runtime.load_with_config(Wat(guest), &41u64).await.unwrap();
+        let count = handle.get_func::<(), u64>("count").await.unwrap();
+        let trap = handle.get_func::<(), u64>("trap").await.unwrap();
+        assert_eq!(count.call_checked(&()).await.unwrap(), 42);
+        assert!(trap.call_checked(&()).await.is_err());
+        assert_eq!(count.call_checked(&()).await.unwrap(), 42);
+    }
+
+    #[tokio::test]
+    async fn restarts_with_the_latest_config() {
+        let runtime = Runtime::<Raw>::new().unwrap();
+        // `Ok(())` at offset 16, `on_load` adds 100 to the count
+        let guest = counter_with(
+            r#"(func (export "_plugy_init") (param $value i64) (result i64)
+                (global.set $count (i64.load (i32.wrap_i64 (local.get $value))))
+                (i64.const 0))
+            (func (export "_plugy_hook_on_config_change") (param $value i64) (result i64)
+                (global.set $count (i64.load (i32.wrap_i64 (local.get $value))))
+                (i64.const 0x400000010))
+            (func (export "_plugy_hook_on_load") (param i64) (result i64)
+                (global.set $count (i64.add (global.get $count) (i64.const 100)))
+                (i64.const 0x400000010))"#,
+        );
+        let handle = runtime.load_with_config(Wat(guest), &1u64).await.unwrap();
+        let count = handle.get_func::<(), u64>("count").await.unwrap();
+        let trap = handle.get_func::<(), u64>("trap").await.unwrap();
+        assert_eq!(count.call_checked(&()).await.unwrap(), 102);
+        runtime.reconfigure("wat", &41u64).await.unwrap();
+        assert_eq!(count.call_checked(&()).await.unwrap(), 42);
+        assert!(trap.call_checked(&()).await.is_err());
+        assert_eq!(count.call_checked(&()).await.unwrap(), 142);
+    }
+
+    #[tokio::test]
+    async fn guests_read_and_update_their_data() {
+        // `save` stores the blob `[7]` laid out at offset 64, `load` returns
+        // the envelope of the blob read back
+        let guest = r#"
+            (module
+                (import "plugy:plugin_data" "get" (func $get (param i64) (result i64)))
+                (import "plugy:plugin_data" "set" (func $set (param i64) (result i64)))
+                (memory (export "memory") 1)
+                (global $heap (mut i32) (i32.const 1024))
+                (func (export "_plugy_abi_version") (result i32)
+                    (i32.const 1))
+                (data (i32.const 64) "\01\00\00\00\00\00\00\00\07")
+                (func (export "alloc") (param $len i32) (result i32)
+                    (local $ptr i32)
+                    (local.set $ptr (global.get $heap))
+                    (global.set $heap (i32.add (global.get $heap) (local.get $len)))
+                    (local.get $ptr))
+                (func (export "dealloc") (param i64))
+                (func (export "_plugy_guest_Store::save") (param i64) (result i64)
+                    (call $set (i64.const 0x900000040)))
+                (func (export "_plugy_guest_Store::load") (param i64) (result i64)
+                    (call $get (i64.const 0))))
+        "#;
+        let runtime = Runtime::<Raw>::new().unwrap().context(PluginData);
+        let handle = runtime.load(Wat(guest)).await.unwrap();
+        let save = handle
+            .get_func::<(), Result<(), ContextError>>("save")
+            .await
+            .unwrap();
+        let load = handle
+            .get_func::<(), Result<Vec<u8>, ContextError>>("load")
+            .await
+            .unwrap();
+        assert_eq!(load.call_checked(&()).await.unwrap(), Ok(vec![42]));
+        assert_eq!(save.call_checked(&()).await.unwrap(), Ok(()));
+        assert_eq!(load.call_checked(&()).await.unwrap(), Ok(vec![7]));
+    }
+
+    /// A synchronous context, as `#[context]` links plain functions.
+    struct Clock;
+
+    impl Context for Clock {
+        fn imports(&self) -> Vec<(&'static str, &'static str)> {
+            vec![("plugy:clock", "now")]
+        }
+
+        fn link(&self, linker: &mut Linker) -> anyhow::Result<()> {
+            linker.func_wrap(
+                "plugy:clock",
+                "now",
+                |mut caller: Caller<'_, Plugin>, ptr: u64| {
+                    let result = read_sync_context_args(&mut caller, ptr).map(|_| 42u64);
+                    stash_context_result(&mut caller, result)
+                },
+            )?;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn guests_take_results_of_sync_contexts() {
+        // `now` allocates room for the result and has the host write it there
+        let guest = r#"
+            (module
+                (import "plugy:clock" "now" (func $now (param i64) (result i64)))
+                (import "env" "_plugy_context_take" (func $take (param i64)))
+                (memory (export "memory") 1)
+                (global $heap (mut i32) (i32.const 1024))
+                (func (export "_plugy_abi_version") (result i32)
+                    (i32.const 1))
+                (func $alloc (export "alloc") (param $len i32) (result i32)
+                    (local $ptr i32)
+                    (local.set $ptr (global.get $heap))
+                    (global.set $heap (i32.add (global.get $heap) (local.get $len)))
+                    (local.get $ptr))
+                (func (export "dealloc") (param i64))
+                (func (export "_plugy_guest_Clock::now") (param i64) (result i64)
+                    (local $len i64)
+                    (local $value i64)
+                    (local.set $len (call $now (i64.const 0)))
+                    (local.set $value
+                        (i64.or
+                            (i64.shl (local.get $len) (i64.const 32))
+                            (i64.extend_i32_u (call $alloc (i32.wrap_i64 (local.get $len))))))
+                    (call $take (local.get $value))
+                    (local.get $value)))
+        "#;
+        let runtime = Runtime::<Raw>::new().unwrap().context(Clock);
+        let handle = runtime.load(Wat(guest)).await.unwrap();
+        let now = handle
+            .get_func::<(), Result<u64, ContextError>>("now")
+            .await
+            .unwrap();
+        assert_eq!(now.call_checked(&()).await.unwrap(), Ok(42));
+        assert_eq!(now.call_checked(&()).await.unwrap(), Ok(42));
+    }
+
+    #[test]
+    fn reports_context_collisions() {
+        let runtime = Runtime::<Raw>::new().unwrap().context(PluginData);
+        let Err(e) = runtime.try_context(PluginData) else {
+            panic!("linked `PluginData` twice");
+        };
+        assert_eq!(
+            e.downcast_ref::<ImportCollision>(),
+            Some(&ImportCollision {
+                module: "plugy:plugin_data",
+                name: "get",
+                first: "plugy_runtime::PluginData",
+                second: "plugy_runtime::PluginData",
+            })
+        );
+    }
+
+    #[test]
+    fn returns_linker_errors() {
+        /// Leaves out of `imports` the function it redefines.
+        struct Shadow;
+
+        impl Context for Shadow {
+            fn imports(&self) -> Vec<(&'static str, &'static str)> {
+                Vec::new()
+            }
+
+            fn link(&self, linker: &mut Linker) -> anyhow::Result<()> {
+                linker.func_wrap("env", CONTEXT_TAKE, |_: u64| {})?;
+                Ok(())
+            }
+        }
+
+        assert!(Runtime::<Raw>::new().unwrap().try_context(Shadow).is_err());
+    }
+
+    #[tokio::test]
+    async fn resolves_functions_by_trait() {
+        let runtime = Runtime::<Raw>::new().unwrap();
+        let guest = counter_with(
+            r#"(func (export "_plugy_guest_Counter::name") (param i64) (result i64)
+                (i64.const 0))
+            (func (export "_plugy_guest_Named::name") (param i64) (result i64)
+                (i64.const 0))"#,
+        );
+        let handle = runtime.load(Wat(guest)).await.unwrap();
+        let Err(error) = handle.get_func::<(), ()>("name").await else {
+            panic!("`name` is ambiguous");
+        };
+        assert!(error.to_string().contains("Counter::name, Named::name"));
+        handle.get_func::<(), ()>("Named::name").await.unwrap();
+        let count = handle.get_func::<(), u64>("Counter::count").await.unwrap();
+        assert_eq!(count.call_checked(&()).await.unwrap(), 1);
+        assert!(handle.get_func::<(), ()>("Counter::missing").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn checks_signatures() {
+        let runtime = Runtime::<Raw>::new().unwrap();
+        // The guest was built against signature 7 of `Counter::total`
+        let guest = counter_with(
+            r#"(data (i32.const 128) "\01\00\00\00\00\00\00\00\0e\00\00\00\00\00\00\00Counter::total\07\00\00\00\00\00\00\00")
+            (func (export "_plugy_signatures_Counter") (param i64) (result i64)
+                (i64.const 0x2600000080))
+            (func (export "_plugy_guest_Counter::total") (param i64) (result i64)
+                (i64.const 0x800000000))"#,
+        );
+        let handle = runtime.load(Wat(guest)).await.unwrap();
+        handle
+            .get_func_with_signature::<(), u64>("total", 7)
+            .await
+            .unwrap();
+        let Err(error) = handle
+            .get_func_with_signature::<(), u64>("Counter::total", 8)
+            .await
+        else {
+            panic!("the signatures differ");
+        };
+        assert_eq!(
+            error.downcast_ref::<PluginError>(),
+            Some(&PluginError::SignatureMismatch {
+                plugin: "wat",
+                method: "Counter::total".to_owned(),
+            })
+        );
+        // Functions without a listed signature are not checked
+        handle
+            .get_func_with_signature::<(), u64>("count", 8)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn dispatches_default_methods() {
+        let runtime = Runtime::<Raw>::new().unwrap();
+        // The dispatcher answers the length of what it was handed, which is
+        // the method name and the `u64` input. It also reaches `Named::label`
+        // of a supertrait.
+        let guest = counter_with(
+            r#"(data (i32.const 128) "\02\00\00\00\00\00\00\00\0f\00\00\00\00\00\00\00Counter::double\0c\00\00\00\00\00\00\00Named::label")
+            (func (export "_plugy_methods_Counter") (param i64) (result i64)
+                (i64.const 0x3300000080))
+            (func (export "_plugy_dispatch_Counter") (param $value i64) (result i64)
+                (i64.store (i32.const 8) (i64.shr_u (local.get $value) (i64.const 32)))
+                (i64.const 0x800000008))"#,
+        );
+        let handle = runtime.load(Wat(guest)).await.unwrap();
+        assert!(handle.has_method("Counter::double").await);
+        assert!(handle.has_method("double").await);
+        assert!(handle.has_method("count").await);
+        assert!(!handle.has_method("Counter::triple").await);
+        let double = handle.get_func::<u64, u64>("double").await.unwrap();
+        // 8 + 15 bytes of name, 8 bytes of input
+        assert_eq!(double.call_checked(&2).await.unwrap(), 31);
+        let label = handle.get_func::<u64, u64>("Named::label").await.unwrap();
+        assert_eq!(label.call_checked(&2).await.unwrap(), 28);
+    }
 }
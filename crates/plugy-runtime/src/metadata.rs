@@ -0,0 +1,169 @@
+use anyhow::Context as ErrorContext;
+
+/// The custom section `#[plugin_metadata]` and `#[plugin_impl]` write to.
+///
+/// The linker concatenates every static placed in it, so the section holds a
+/// flat sequence of records: a kind byte, a little endian `u32` length and that
+/// many bytes of UTF-8.
+const SECTION: &str = "plugy_metadata";
+
+const NAME: u8 = 1;
+const VERSION: u8 = 2;
+const AUTHOR: u8 = 3;
+const DESCRIPTION: u8 = 4;
+const TRAIT: u8 = 5;
+
+/// What a plugin tells about itself through `#[plugin_metadata]`, along with
+/// the plugin traits it implements with `#[plugin_impl]`.
+///
+/// It is read from the bytes of the module, so plugins can be listed and
+/// validated without being compiled or run.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use plugy_runtime::PluginMetadata;
+///
+/// let bytes = std::fs::read("target/wasm32-unknown-unknown/debug/foo_plugin.wasm").unwrap();
+/// let metadata = PluginMetadata::from_bytes(&bytes).unwrap();
+/// assert!(metadata.implements("Greeter"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PluginMetadata {
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub authors: Vec<String>,
+    pub description: Option<String>,
+    /// The implemented plugin traits, as `Trait` or `Trait<Args>` for the
+    /// instantiations of generic traits.
+    pub traits: Vec<String>,
+}
+
+impl PluginMetadata {
+    /// Reads the metadata embedded in a compiled wasm module.
+    ///
+    /// Modules without metadata, such as plugins built without
+    /// `#[plugin_metadata]`, yield empty metadata.
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        let mut reader = Reader(bytes);
+        anyhow::ensure!(
+            reader.take(4)? == b"\0asm" && reader.take(4)? == [1, 0, 0, 0],
+            "not a wasm module"
+        );
+        let mut metadata = PluginMetadata::default();
+        while !reader.0.is_empty() {
+            let id = reader.take(1)?[0];
+            let len = reader.leb128()?;
+            let mut section = Reader(reader.take(len)?);
+            if id != 0 {
+                continue;
+            }
+            let name_len = section.leb128()?;
+            if section.take(name_len)? == SECTION.as_bytes() {
+                metadata.read_records(section)?;
+            }
+        }
+        Ok(metadata)
+    }
+
+    /// Whether the plugin implements the plugin trait `name`.
+    pub fn implements(&self, name: &str) -> bool {
+        self.traits.iter().any(|t| t == name)
+    }
+
+    fn read_records(&mut self, mut records: Reader<'_>) -> anyhow::Result<()> {
+        while !records.0.is_empty() {
+            let kind = records.take(1)?[0];
+            let len = u32::from_le_bytes(records.take(4)?.try_into()?);
+            let value = std::str::from_utf8(records.take(len)?)
+                .context("invalid plugin metadata")?
+                .to_owned();
+            match kind {
+                NAME => self.name = Some(value),
+                VERSION => self.version = Some(value),
+                AUTHOR => self.authors.push(value),
+                DESCRIPTION => self.description = Some(value),
+                TRAIT if !self.implements(&value) => self.traits.push(value),
+                // Kinds of later versions are skipped
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: u32) -> anyhow::Result<&'a [u8]> {
+        anyhow::ensure!(self.0.len() >= len as usize, "truncated wasm module");
+        let (head, tail) = self.0.split_at(len as usize);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn leb128(&mut self) -> anyhow::Result<u32> {
+        let mut value = 0u32;
+        for shift in (0..35).step_by(7) {
+            let byte = self.take(1)?[0];
+            value |= u32::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        anyhow::bail!("invalid wasm module")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn record(kind: u8, value: &str) -> Vec<u8> {
+        let mut record = vec![kind];
+        record.extend((value.len() as u32).to_le_bytes());
+        record.extend(value.as_bytes());
+        record
+    }
+
+    fn custom_section(name: &str, payload: &[u8]) -> Vec<u8> {
+        let mut section = vec![0, (1 + name.len() + payload.len()) as u8, name.len() as u8];
+        section.extend(name.as_bytes());
+        section.extend(payload);
+        section
+    }
+
+    #[test]
+    fn reads_metadata() {
+        let mut module = b"\0asm\x01\0\0\0".to_vec();
+        // An empty type section
+        module.extend([1, 1, 0]);
+        module.extend(custom_section("name", b"ignored"));
+        let records = [
+            record(NAME, "foo"),
+            record(VERSION, "0.1.0"),
+            record(AUTHOR, "Ada"),
+            record(AUTHOR, "Grace"),
+            record(TRAIT, "Greeter"),
+            record(42, "from the future"),
+        ]
+        .concat();
+        module.extend(custom_section(SECTION, &records));
+        module.extend(custom_section(SECTION, &record(TRAIT, "Codec<u32>")));
+        let metadata = PluginMetadata::from_bytes(&module).unwrap();
+        assert_eq!(
+            metadata,
+            PluginMetadata {
+                name: Some("foo".to_owned()),
+                version: Some("0.1.0".to_owned()),
+                authors: vec!["Ada".to_owned(), "Grace".to_owned()],
+                description: None,
+                traits: vec!["Greeter".to_owned(), "Codec<u32>".to_owned()],
+            }
+        );
+        assert!(metadata.implements("Codec<u32>"));
+        assert!(PluginMetadata::from_bytes(b"(module)").is_err());
+        assert!(PluginMetadata::from_bytes(&module[..module.len() - 1]).is_err());
+    }
+}
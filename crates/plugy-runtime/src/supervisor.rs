@@ -0,0 +1,225 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use wasmtime::{Engine, EngineWeak};
+
+/// How long one epoch tick lasts when call timeouts are enforced.
+pub(crate) const TICK: Duration = Duration::from_millis(10);
+
+/// Epoch deadline used when calls are not timed, far enough to never be reached.
+pub(crate) const NO_DEADLINE: u64 = u64::MAX / 2;
+
+/// How a supervised plugin is restarted and when it gets quarantined.
+///
+/// Every trap or timeout counts as a failure. The plugin is restarted after a
+/// backoff delay that grows with consecutive failures, and quarantined once
+/// `max_failures` failures happened within `window`. A quarantined plugin fails
+/// every call with [`PluginError::Quarantined`](crate::PluginError::Quarantined)
+/// until [`Runtime::reenable`](crate::Runtime::reenable) is called.
+///
+/// # Example
+///
+/// ```rust
+/// use plugy_runtime::{Backoff, RecoveryPolicy, Runtime, SupervisorPolicy};
+/// use std::time::Duration;
+///
+/// trait Greeter {
+///     fn greet(&self);
+/// }
+/// let runtime = Runtime::<Box<dyn Greeter>>::new()
+///     .unwrap()
+///     .recovery(RecoveryPolicy::Supervised(SupervisorPolicy {
+///         max_failures: 3,
+///         window: Duration::from_secs(60),
+///         backoff: Backoff::default(),
+///         call_timeout: Some(Duration::from_secs(5)),
+///     }));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SupervisorPolicy {
+    /// Failures tolerated within `window` before the plugin is quarantined.
+    pub max_failures: u32,
+    /// The sliding window failures are counted in.
+    pub window: Duration,
+    /// The delay applied before restarting a failed plugin.
+    pub backoff: Backoff,
+    /// Interrupts calls running longer than this, counting them as failures.
+    pub call_timeout: Option<Duration>,
+}
+
+impl Default for SupervisorPolicy {
+    fn default() -> Self {
+        Self {
+            max_failures: 5,
+            window: Duration::from_secs(60),
+            backoff: Backoff::default(),
+            call_timeout: None,
+        }
+    }
+}
+
+/// An exponential backoff between restarts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    /// The delay before the first restart.
+    pub initial: Duration,
+    /// The upper bound of the delay.
+    pub max: Duration,
+    /// The factor the delay grows by after each consecutive failure.
+    pub multiplier: u32,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(100),
+            max: Duration::from_secs(30),
+            multiplier: 2,
+        }
+    }
+}
+
+impl Backoff {
+    /// The delay before the restart following `attempt` consecutive failures.
+    fn delay(&self, attempt: u32) -> Duration {
+        let factor = self
+            .multiplier
+            .checked_pow(attempt.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.initial
+            .checked_mul(factor)
+            .map_or(self.max, |delay| delay.min(self.max))
+    }
+}
+
+/// What a supervisor decided after a failure.
+pub(crate) enum Verdict {
+    /// Restart once the delay has elapsed.
+    Restart(Duration),
+    /// Too many failures, stop running the plugin.
+    Quarantine(usize),
+}
+
+/// Why a supervisor refused a call.
+pub(crate) enum Refusal {
+    Quarantined,
+    BackingOff(Duration),
+}
+
+#[derive(Default)]
+struct Health {
+    failures: VecDeque<Instant>,
+    consecutive: u32,
+    retry_at: Option<Instant>,
+    quarantined: bool,
+}
+
+/// Tracks the failures of a single plugin.
+pub(crate) struct Supervisor {
+    policy: SupervisorPolicy,
+    health: Mutex<Health>,
+}
+
+impl Supervisor {
+    pub(crate) fn new(policy: SupervisorPolicy) -> Self {
+        Self {
+            policy,
+            health: Mutex::new(Health::default()),
+        }
+    }
+
+    fn health(&self) -> std::sync::MutexGuard<'_, Health> {
+        self.health.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The epoch deadline to apply to the next call.
+    pub(crate) fn deadline(&self) -> u64 {
+        self.policy.call_timeout.map_or(NO_DEADLINE, |timeout| {
+            (timeout.as_nanos() / TICK.as_nanos()).max(1) as u64
+        })
+    }
+
+    /// Checks whether a call may go ahead.
+    pub(crate) fn admit(&self) -> Result<(), Refusal> {
+        let health = self.health();
+        if health.quarantined {
+            return Err(Refusal::Quarantined);
+        }
+        match health.retry_at {
+            Some(at) => match at.checked_duration_since(Instant::now()) {
+                Some(remaining) if !remaining.is_zero() => Err(Refusal::BackingOff(remaining)),
+                _ => Ok(()),
+            },
+            None => Ok(()),
+        }
+    }
+
+    /// Records a trap or a timeout.
+    pub(crate) fn record_failure(&self) -> Verdict {
+        let now = Instant::now();
+        let mut health = self.health();
+        health.failures.push_back(now);
+        while health
+            .failures
+            .front()
+            .is_some_and(|at| now.duration_since(*at) > self.policy.window)
+        {
+            health.failures.pop_front();
+        }
+        if health.failures.len() >= self.policy.max_failures as usize {
+            health.quarantined = true;
+            return Verdict::Quarantine(health.failures.len());
+        }
+        health.consecutive += 1;
+        let delay = self.policy.backoff.delay(health.consecutive);
+        health.retry_at = Some(now + delay);
+        Verdict::Restart(delay)
+    }
+
+    /// Records a successful call, which resets the backoff.
+    pub(crate) fn record_success(&self) {
+        let mut health = self.health();
+        health.consecutive = 0;
+        health.retry_at = None;
+    }
+
+    pub(crate) fn is_quarantined(&self) -> bool {
+        self.health().quarantined
+    }
+
+    /// Lifts a quarantine and forgets past failures.
+    pub(crate) fn reenable(&self) {
+        *self.health() = Health::default();
+    }
+}
+
+/// Advances the epoch of `engine` every [`TICK`] for as long as it is alive.
+pub(crate) fn start_ticker(engine: &Engine) {
+    let engine: EngineWeak = engine.weak();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(TICK);
+        match engine.upgrade() {
+            Some(engine) => engine.increment_epoch(),
+            None => break,
+        }
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn backoff() {
+        let backoff = Backoff {
+            initial: Duration::from_millis(100),
+            max: Duration::from_secs(1),
+            multiplier: 3,
+        };
+        assert_eq!(backoff.delay(1), Duration::from_millis(100));
+        assert_eq!(backoff.delay(2), Duration::from_millis(300));
+        assert_eq!(backoff.delay(3), Duration::from_millis(900));
+        assert_eq!(backoff.delay(4), Duration::from_secs(1));
+        assert_eq!(backoff.delay(100), Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,125 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::{pin, Pin};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Condvar, Mutex, MutexGuard, OnceLock};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+/// The pending sleeps, woken by a single deadline thread.
+#[derive(Default)]
+struct Timers {
+    /// The waker of every registered sleep, by deadline and then creation order.
+    pending: Mutex<BTreeMap<(Instant, u64), Waker>>,
+    /// Notified when an earlier deadline comes in.
+    changed: Condvar,
+}
+
+impl Timers {
+    /// The shared timers, starting the deadline thread on first use.
+    fn get() -> &'static Timers {
+        static TIMERS: OnceLock<Timers> = OnceLock::new();
+        let mut started = false;
+        let timers = TIMERS.get_or_init(|| {
+            started = true;
+            Timers::default()
+        });
+        if started {
+            std::thread::spawn(|| timers.run());
+        }
+        timers
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<(Instant, u64), Waker>> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Wakes the sleeps as their deadlines pass.
+    fn run(&self) {
+        let mut pending = self.lock();
+        loop {
+            let now = Instant::now();
+            while let Some(entry) = pending.first_entry() {
+                if entry.key().0 > now {
+                    break;
+                }
+                entry.remove().wake();
+            }
+            pending = match pending.keys().next() {
+                Some(&(deadline, _)) => {
+                    self.changed
+                        .wait_timeout(pending, deadline - now)
+                        .unwrap_or_else(|e| e.into_inner())
+                        .0
+                }
+                None => self
+                    .changed
+                    .wait(pending)
+                    .unwrap_or_else(|e| e.into_inner()),
+            };
+        }
+    }
+}
+
+/// A future completing after a delay, independent of any async executor.
+///
+/// Sleeps are woken by one thread shared by the whole process, and dropping a
+/// sleep cancels it.
+pub(crate) struct Sleep {
+    deadline: Instant,
+    /// Tells apart sleeps sharing a deadline.
+    id: u64,
+    registered: bool,
+}
+
+impl Sleep {
+    pub(crate) fn new(delay: Duration) -> Self {
+        static IDS: AtomicU64 = AtomicU64::new(0);
+        Self {
+            deadline: Instant::now() + delay,
+            id: IDS.fetch_add(1, Ordering::Relaxed),
+            registered: false,
+        }
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if Instant::now() >= self.deadline {
+            return Poll::Ready(());
+        }
+        let timers = Timers::get();
+        let mut pending = timers.lock();
+        let key = (self.deadline, self.id);
+        let earliest = pending.keys().next().is_none_or(|first| key < *first);
+        pending.insert(key, cx.waker().clone());
+        self.registered = true;
+        if earliest {
+            timers.changed.notify_one();
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        if self.registered {
+            Timers::get().lock().remove(&(self.deadline, self.id));
+        }
+    }
+}
+
+/// Runs `future` to completion unless `timeout` elapses first.
+pub(crate) async fn timeout<F: Future>(timeout: Duration, future: F) -> Option<F::Output> {
+    let mut future = pin!(future);
+    let mut sleep = Sleep::new(timeout);
+    std::future::poll_fn(|cx| {
+        if let Poll::Ready(output) = future.as_mut().poll(cx) {
+            return Poll::Ready(Some(output));
+        }
+        Pin::new(&mut sleep).poll(cx).map(|_| None)
+    })
+    .await
+}
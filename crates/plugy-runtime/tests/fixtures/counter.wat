@@ -0,0 +1,20 @@
+;; A guest implementing `Counter` by hand, for the host side tests
+(module
+    (memory (export "memory") 1)
+    (global $heap (mut i32) (i32.const 1024))
+    (global $count (mut i64) (i64.const 0))
+    (func (export "_plugy_abi_version") (result i32)
+        (i32.const 1))
+    (func (export "alloc") (param $len i32) (result i32)
+        (local $ptr i32)
+        (local.set $ptr (global.get $heap))
+        (global.set $heap (i32.add (global.get $heap) (local.get $len)))
+        (local.get $ptr))
+    (func (export "dealloc") (param i64))
+    (func (export "_plugy_guest_Counter::count") (param i64) (result i64)
+        (global.set $count (i64.add (global.get $count) (i64.const 1)))
+        (i64.store (i32.const 0) (global.get $count))
+        ;; 8 bytes at offset 0
+        (i64.const 0x800000000))
+    (func (export "_plugy_guest_Counter::trap") (param i64) (result i64)
+        unreachable))
@@ -0,0 +1,62 @@
+use plugy::core::PluginLoader;
+use plugy::macros::plugin_import;
+use plugy::runtime::{Plugin, Runtime};
+
+#[plugy::macros::plugin]
+pub trait Counter {
+    fn count(&self) -> u64;
+    fn trap(&self) -> u64;
+}
+
+const COUNTER: &[u8] = include_bytes!("fixtures/counter.wat");
+
+#[plugin_import(file = "tests/fixtures/counter.wat", manifest_dir, name = "counter")]
+struct Named;
+
+#[plugin_import(file = "tests/fixtures/counter.wat", manifest_dir, data = data)]
+struct Unnamed {
+    data: Vec<u8>,
+}
+
+#[plugin_import(file = "tests/fixtures/counter.wat", embed)]
+struct Embedded;
+
+#[plugin_import(file = "tests/fixtures/missing.wat", env = "PLUGY_TEST_COUNTER")]
+struct Overridden;
+
+#[tokio::test]
+async fn names_plugins_after_the_loader() {
+    let runtime = Runtime::<Box<dyn Counter>>::new().unwrap();
+    assert_eq!(Named.name(), "counter");
+    let handle = runtime.load(Named).await.unwrap();
+    assert_eq!(handle.count().await.unwrap(), 1);
+    assert!(!runtime.is_quarantined("counter"));
+    runtime.unload("counter").await.unwrap();
+
+    // Without a `name`, the loader and the plugin are named after the struct
+    let plugin = Unnamed { data: vec![] };
+    assert_eq!(plugin.name(), "Unnamed");
+    assert_eq!(Plugin::from(Unnamed { data: vec![] }).name, "Unnamed");
+    runtime.load_with(plugin).await.unwrap();
+    runtime.reconfigure("Unnamed", &()).await.unwrap();
+    runtime.unload("Unnamed").await.unwrap();
+}
+
+#[tokio::test]
+async fn reads_modules_from_the_manifest_dir() {
+    assert_eq!(Named.bytes().await.unwrap(), COUNTER);
+}
+
+#[tokio::test]
+async fn embeds_modules() {
+    assert_eq!(Embedded.bytes().await.unwrap(), COUNTER);
+}
+
+#[tokio::test]
+async fn overrides_the_path_from_the_environment() {
+    assert!(Overridden.bytes().await.is_err());
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/counter.wat");
+    std::env::set_var("PLUGY_TEST_COUNTER", path);
+    assert_eq!(Overridden.bytes().await.unwrap(), COUNTER);
+    std::env::remove_var("PLUGY_TEST_COUNTER");
+}
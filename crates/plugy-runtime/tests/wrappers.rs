@@ -0,0 +1,56 @@
+use plugy::core::PluginLoader;
+use plugy::runtime::{Backoff, Plugin, PluginError, RecoveryPolicy, Runtime, SupervisorPolicy};
+use std::{future::Future, pin::Pin, time::Duration};
+
+#[plugy::macros::plugin]
+pub trait Counter {
+    fn count(&self) -> u64;
+    fn trap(&self) -> u64;
+}
+
+const COUNTER: &str = include_str!("fixtures/counter.wat");
+
+struct Wat;
+
+impl PluginLoader for Wat {
+    fn bytes(&self) -> Pin<Box<dyn Future<Output = Result<Vec<u8>, anyhow::Error>>>> {
+        Box::pin(async { Ok(COUNTER.as_bytes().to_vec()) })
+    }
+
+    fn name(&self) -> &'static str {
+        "wat"
+    }
+}
+
+impl From<Wat> for Plugin {
+    fn from(_: Wat) -> Self {
+        Plugin {
+            name: "wat".into(),
+            plugin_type: "wat".into(),
+            data: vec![],
+        }
+    }
+}
+
+#[tokio::test]
+async fn wrappers_return_errors_of_quarantined_plugins() {
+    let runtime = Runtime::<Box<dyn Counter>>::new()
+        .unwrap()
+        .recovery(RecoveryPolicy::Supervised(SupervisorPolicy {
+            max_failures: 1,
+            backoff: Backoff {
+                initial: Duration::ZERO,
+                ..Backoff::default()
+            },
+            ..SupervisorPolicy::default()
+        }));
+    let handle = runtime.load(Wat).await.unwrap();
+    assert_eq!(handle.count().await.unwrap(), 1);
+    assert!(handle.trap().await.is_err());
+    assert!(runtime.is_quarantined("wat"));
+    let error = handle.count().await.unwrap_err();
+    assert_eq!(
+        error.downcast_ref::<PluginError>(),
+        Some(&PluginError::Quarantined("wat"))
+    );
+}
@@ -1,17 +1,26 @@
-use plugy::macros::plugin_impl;
+use plugy::macros::{plugin_hooks, plugin_impl, plugin_metadata};
 use serde::Deserialize;
 use shared::{fetcher::sync::Fetcher, logger::sync::Logger, Greeter};
 
-#[derive(Debug, Deserialize)]
+#[plugin_metadata(description = "Greets people")]
+#[derive(Debug, Default, Deserialize)]
 struct FooPlugin;
 
 #[plugin_impl]
 impl Greeter for FooPlugin {
     fn greet(&self, name: String, last_name: Option<String>) -> String {
-        let res = Fetcher::fetch("http://example.com".to_owned());
+        let res = Fetcher::fetch("http://example.com".to_owned()).unwrap_or_else(|e| e);
         Logger::log(&res);
         let last_name = last_name.unwrap_or_default();
 
         format!("Hello From Foo Plugin to {name} {last_name}")
     }
 }
+
+#[plugin_hooks]
+impl FooPlugin {
+    fn on_load(&self) -> Result<(), String> {
+        Logger::log("Foo Plugin loaded");
+        Ok(())
+    }
+}
@@ -13,8 +13,12 @@ pub struct Fetcher;
 
 #[plugy::macros::context(data = Addr)]
 impl Fetcher {
-    pub async fn fetch(_: &mut plugy::runtime::Caller<'_, Plugin<Addr>>, url: String) -> String {
-        reqwest::get(url).await.unwrap().text().await.unwrap()
+    pub async fn fetch(
+        _: &mut plugy::runtime::Caller<'_, Plugin<Addr>>,
+        url: String,
+    ) -> Result<String, String> {
+        let res = reqwest::get(url).await.map_err(|e| e.to_string())?;
+        res.text().await.map_err(|e| e.to_string())
     }
 }
 
@@ -6,20 +6,11 @@ use plugy::{
 use shared::{Addr, Fetcher, Greeter, Logger, Printer};
 use xtra::Mailbox;
 
-#[plugin_import(file = "target/wasm32-unknown-unknown/debug/foo_plugin.wasm")]
+#[plugin_import(file = "target/wasm32-unknown-unknown/debug/foo_plugin.wasm", data = addr)]
 #[derive(Debug)]
 struct FooPlugin {
     addr: Addr,
 }
-impl From<FooPlugin> for Plugin<Addr> {
-    fn from(val: FooPlugin) -> Self {
-        Plugin {
-            name: "FooPlugin".to_string(),
-            data: val.addr,
-            plugin_type: "FooPlugin".to_string(),
-        }
-    }
-}
 
 #[tokio::main]
 async fn main() {
@@ -29,15 +20,17 @@ async fn main() {
         .context(Fetcher)
         // Include the logger context
         .context(Logger);
-    let handle = runtime
-        .load_with(FooPlugin {
-            addr: xtra::spawn_tokio(Printer::default(), Mailbox::unbounded()),
-        })
-        .await
-        .unwrap();
+    let plugin = FooPlugin {
+        addr: xtra::spawn_tokio(Printer::default(), Mailbox::unbounded()),
+    };
+    // Check what the plugin implements before running any of it
+    let metadata = runtime.inspect(&plugin).await.unwrap();
+    assert!(metadata.implements("Greeter"));
+    let handle = runtime.load_with(plugin).await.unwrap();
     let res = handle
         .greet("Geoff".to_owned(), Some("Mureithi".to_owned()))
-        .await;
+        .await
+        .unwrap();
     println!("{res}");
     assert_eq!(res, "Hello From Foo Plugin to Geoff Mureithi")
 }